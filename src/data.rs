@@ -1,5 +1,6 @@
+use log::{info, warn};
 use serde_json::Value;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 pub fn sanitize(s: &str) -> String {
     s.chars()
@@ -15,14 +16,112 @@ pub fn sanitize(s: &str) -> String {
         .collect()
 }
 
-fn format_float(f: f64) -> String {
-    let s = format!("{:.10}", f);
-    let s = s.trim_end_matches('0');
-    let s = s.trim_end_matches('.');
-    s.to_string()
+/// Replace every run of whitespace (line breaks and tabs included) in `s`
+/// with a single space and trim the ends.
+pub fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
-fn extract_array_element(elem: &Value) -> Option<String> {
+/// Sanitized, trimmed text of a string cell, collapsed when
+/// `fmt.collapse_whitespace` is set.
+fn clean_text(s: &str, fmt: &ValueFormat) -> String {
+    let s = sanitize(s.trim());
+    if fmt.collapse_whitespace {
+        collapse_whitespace(&s)
+    } else {
+        s
+    }
+}
+
+/// Replace `\r\n`, `\n` and `\r` in `s` with `token`.
+pub fn escape_newlines(s: &str, token: &str) -> String {
+    s.replace("\r\n", token).replace(['\n', '\r'], token)
+}
+
+/// Cell formatting knobs shared by the row builders. `ValueFormat::DEFAULT`
+/// matches the CLI defaults.
+#[derive(Clone, Copy, Debug)]
+pub struct ValueFormat<'a> {
+    /// Joins array elements and repeated same-language trade names.
+    pub array_sep: &'a str,
+    /// Decimal places floats are rounded to before trailing zeros are
+    /// trimmed; `None` keeps the shortest representation that round-trips.
+    pub float_precision: Option<usize>,
+    /// Language order for the synthesized `tradeName_preferred` column;
+    /// empty means no such column.
+    pub lang_priority: &'a [String],
+    /// Replaces line breaks inside cell values so every row stays on one
+    /// line; `None` keeps them (CSV cells are then quoted).
+    pub newline_token: Option<&'a str>,
+    /// Levels of object-valued top-level item fields expanded into
+    /// `parent.child` columns; 0 keeps them as one raw-JSON cell.
+    pub flatten_depth: usize,
+    /// Split language-tagged array fields (other than trade names) into one
+    /// `<field>_<LANG>` column per language instead of `LANG: text` cells.
+    pub split_lang_arrays: bool,
+    /// Trade-name languages to keep (matched case-insensitively); names in
+    /// other languages are dropped. Empty keeps every language.
+    pub langs: &'a [String],
+    /// Collapse whitespace runs inside string values to one space (see
+    /// `collapse_whitespace`).
+    pub collapse_whitespace: bool,
+}
+
+impl ValueFormat<'_> {
+    /// True if trade names in `lang` are kept (see `langs`).
+    pub fn keeps_lang(&self, lang: &str) -> bool {
+        self.langs.is_empty() || self.langs.iter().any(|l| l.eq_ignore_ascii_case(lang))
+    }
+}
+
+impl ValueFormat<'static> {
+    pub const DEFAULT: ValueFormat<'static> = ValueFormat {
+        array_sep: " | ",
+        float_precision: Some(10),
+        lang_priority: &[],
+        newline_token: None,
+        flatten_depth: 1,
+        split_lang_arrays: false,
+        langs: &[],
+        collapse_whitespace: false,
+    };
+}
+
+/// Synthesized column holding the first non-empty trade name in
+/// `ValueFormat::lang_priority` order.
+pub const PREFERRED_TRADE_NAME: &str = "tradeName_preferred";
+
+/// First non-empty trade name of a udiDis entry in `priority` order.
+fn preferred_trade_name(tn_map: &HashMap<String, String>, priority: &[String]) -> String {
+    priority
+        .iter()
+        .find_map(|lang| {
+            tn_map
+                .iter()
+                .find(|(l, text)| l.eq_ignore_ascii_case(lang) && !text.is_empty())
+                .map(|(_, text)| text.clone())
+        })
+        .unwrap_or_default()
+}
+
+impl Default for ValueFormat<'static> {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+fn format_float(f: f64, precision: Option<usize>) -> String {
+    let Some(precision) = precision else {
+        return f.to_string();
+    };
+    let s = format!("{:.*}", precision, f);
+    if !s.contains('.') {
+        return s;
+    }
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn extract_array_element(elem: &Value, fmt: &ValueFormat) -> Option<String> {
     match elem {
         Value::Object(obj) => {
             let text = obj
@@ -30,7 +129,7 @@ fn extract_array_element(elem: &Value) -> Option<String> {
                 .or_else(|| obj.get("value"))
                 .or_else(|| obj.get("name"))
                 .and_then(|v| v.as_str())
-                .map(|s| sanitize(s.trim()))
+                .map(|s| clean_text(s, fmt))
                 .unwrap_or_default();
 
             let lang = obj
@@ -47,7 +146,7 @@ fn extract_array_element(elem: &Value) -> Option<String> {
             }
         }
         Value::String(s) => {
-            let t = sanitize(s.trim());
+            let t = clean_text(s, fmt);
             if t.is_empty() {
                 None
             } else {
@@ -58,7 +157,7 @@ fn extract_array_element(elem: &Value) -> Option<String> {
             if let Some(i) = n.as_i64() {
                 Some(i.to_string())
             } else if let Some(f) = n.as_f64() {
-                Some(format_float(f))
+                Some(format_float(f, fmt.float_precision))
             } else {
                 Some(n.to_string())
             }
@@ -77,36 +176,460 @@ fn extract_array_element(elem: &Value) -> Option<String> {
 }
 
 pub fn value_to_string(val: &Value) -> String {
-    match val {
+    value_to_string_with(val, &ValueFormat::DEFAULT)
+}
+
+pub fn value_to_string_with(val: &Value, fmt: &ValueFormat) -> String {
+    let s = match val {
         Value::Null => String::new(),
         Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
         Value::Number(n) => {
             if let Some(i) = n.as_i64() {
                 i.to_string()
             } else if let Some(f) = n.as_f64() {
-                format_float(f)
+                format_float(f, fmt.float_precision)
             } else {
                 n.to_string()
             }
         }
-        Value::String(s) => sanitize(s.trim()),
+        Value::String(s) => clean_text(s, fmt),
         Value::Array(arr) => {
-            let parts: Vec<String> = arr.iter().filter_map(extract_array_element).collect();
-            parts.join(" | ")
+            let parts: Vec<String> = arr
+                .iter()
+                .filter_map(|elem| extract_array_element(elem, fmt))
+                .collect();
+            parts.join(fmt.array_sep)
         }
         Value::Object(_) => sanitize(&val.to_string()),
+    };
+    match fmt.newline_token {
+        Some(token) if s.contains(['\n', '\r']) => escape_newlines(&s, token),
+        _ => s,
     }
 }
 
 pub fn get_field(obj: &Value, key: &str) -> String {
+    get_field_with(obj, key, &ValueFormat::DEFAULT)
+}
+
+pub fn get_field_with(obj: &Value, key: &str, fmt: &ValueFormat) -> String {
     match obj.get(key) {
-        Some(val) => value_to_string(val),
+        Some(val) => value_to_string_with(val, fmt),
         None => String::new(),
     }
 }
 
+/// Drop items sharing the same `key` value, keeping the last occurrence's
+/// content at the position the key first appeared. Items without the key
+/// are kept untouched. Returns the deduplicated items and the key of every
+/// dropped duplicate, in input order.
+pub fn dedup_by_key(values: Vec<Value>, key: &str) -> (Vec<Value>, Vec<String>) {
+    let mut slot_by_key: HashMap<String, usize> = HashMap::new();
+    let mut out: Vec<Value> = Vec::with_capacity(values.len());
+    let mut dropped = Vec::new();
+
+    for item in values {
+        let k = match item.get(key) {
+            Some(v) if !v.is_null() => value_to_string(v),
+            _ => {
+                out.push(item);
+                continue;
+            }
+        };
+        match slot_by_key.get(&k) {
+            Some(&slot) => {
+                out[slot] = item;
+                dropped.push(k);
+            }
+            None => {
+                slot_by_key.insert(k, out.len());
+                out.push(item);
+            }
+        }
+    }
+
+    (out, dropped)
+}
+
+/// Values of the `keys` columns (joined with `/`) that occur in more than
+/// one of the built rows, with their row counts, in order of first
+/// appearance. Rows with an empty key value are ignored; a missing column
+/// yields nothing.
+pub fn duplicate_row_keys(
+    headers: &[String],
+    rows: &[Vec<String>],
+    keys: &[&str],
+) -> Vec<(String, usize)> {
+    let Some(idx) = keys
+        .iter()
+        .map(|k| headers.iter().position(|h| h == k))
+        .collect::<Option<Vec<usize>>>()
+    else {
+        return Vec::new();
+    };
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    for row in rows {
+        let parts: Vec<&str> = idx
+            .iter()
+            .map(|&i| row.get(i).map_or("", String::as_str))
+            .collect();
+        if parts[0].is_empty() {
+            continue;
+        }
+        let value = parts.join("/");
+        let count = counts.entry(value.clone()).or_default();
+        if *count == 0 {
+            order.push(value);
+        }
+        *count += 1;
+    }
+    order
+        .into_iter()
+        .filter(|v| counts[v] > 1)
+        .map(|v| {
+            let n = counts[&v];
+            (v, n)
+        })
+        .collect()
+}
+
+/// Warn about [`duplicate_row_keys`] with up to five examples; with `fail`
+/// (`--fail-on-dup-key`) they are an error instead.
+pub fn check_duplicate_row_keys(
+    headers: &[String],
+    rows: &[Vec<String>],
+    keys: &[&str],
+    fail: bool,
+) -> Result<(), String> {
+    let dups = duplicate_row_keys(headers, rows, keys);
+    if dups.is_empty() {
+        return Ok(());
+    }
+    let dup_rows: usize = dups.iter().map(|(_, n)| n).sum();
+    let examples: Vec<String> = dups
+        .iter()
+        .take(5)
+        .map(|(code, n)| format!("{} ({} rows)", code, n))
+        .collect();
+    let msg = format!(
+        "{} {} value(s) occur in more than one row ({} rows), e.g. {}",
+        dups.len(),
+        keys.join("/"),
+        dup_rows,
+        examples.join(", ")
+    );
+    if fail {
+        return Err(msg);
+    }
+    warn!("{}", msg);
+    Ok(())
+}
+
+/// Sort `rows` by the `columns` (`--sort-by`), compared as plain strings
+/// left to right. The sort is stable and empty values sort first, so the
+/// same data always comes out in the same order. Unknown columns are an
+/// error.
+pub fn sort_rows(
+    headers: &[String],
+    rows: &mut [Vec<String>],
+    columns: &[String],
+) -> Result<(), String> {
+    sort_rows_by(headers, rows, columns, |row| row)
+}
+
+/// [`sort_rows`] for records that carry a row, e.g. a row paired with
+/// other per-row data that has to stay aligned with it.
+pub fn sort_rows_by<T>(
+    headers: &[String],
+    records: &mut [T],
+    columns: &[String],
+    row: impl Fn(&T) -> &[String],
+) -> Result<(), String> {
+    let idx: Vec<usize> = columns
+        .iter()
+        .map(|c| {
+            headers.iter().position(|h| h == c).ok_or_else(|| {
+                format!(
+                    "Unknown column '{}' in --sort-by (available: {})",
+                    c,
+                    headers.join(", ")
+                )
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    records.sort_by(|a, b| {
+        let (a, b) = (row(a), row(b));
+        idx.iter()
+            .map(|&i| a[i].cmp(&b[i]))
+            .find(|o| o.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(())
+}
+
+/// Keys a UDI item may carry its device status under, in lookup order.
+const STATUS_KEYS: [&str; 2] = ["status", "deviceStatus"];
+
+/// A `--company` pattern: `/regex/` or a plain substring, both
+/// case-insensitive.
+enum CompanyPattern {
+    Substring(String),
+    Regex(regex::Regex),
+}
+
+impl CompanyPattern {
+    fn parse(pattern: &str) -> Result<Self, String> {
+        match pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+            Some(re) if !re.is_empty() => regex::RegexBuilder::new(re)
+                .case_insensitive(true)
+                .build()
+                .map(CompanyPattern::Regex)
+                .map_err(|e| format!("Invalid --company regex {}: {}", pattern, e)),
+            _ => Ok(CompanyPattern::Substring(pattern.to_lowercase())),
+        }
+    }
+
+    fn is_match(&self, company: &str) -> bool {
+        match self {
+            CompanyPattern::Substring(s) => company.to_lowercase().contains(s.as_str()),
+            CompanyPattern::Regex(re) => re.is_match(company),
+        }
+    }
+}
+
+/// Canonical form of a risk class so `Class IIa`, `CLASS_IIA` and `IIa`
+/// compare equal: uppercase, without a `CLASS` prefix or separators.
+pub fn normalize_risk_class(s: &str) -> String {
+    let upper = s.trim().to_uppercase();
+    let rest = upper.strip_prefix("CLASS").unwrap_or(&upper);
+    rest.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
+}
+
+/// Item filter settings selected on the command line.
+#[derive(Default)]
+pub struct FilterOptions<'a> {
+    /// `--status` values, matched case-insensitively.
+    pub statuses: &'a [String],
+    /// `--company` patterns (substring or `/regex/`).
+    pub companies: &'a [String],
+    /// `--risk-class` values, compared via [`normalize_risk_class`].
+    pub risk_classes: &'a [String],
+    /// Keep items without a risk class when filtering by risk class.
+    pub include_unknown_risk_class: bool,
+}
+
+/// Post-load item filter (`--status`, `--company`, `--risk-class`). An item
+/// is kept if it passes every filter that is set; within one filter any of
+/// the given values matches. Counts what it sees so the caller can report
+/// how much of the input was kept.
+pub struct ItemFilter {
+    statuses: Vec<String>,
+    companies: Vec<(String, CompanyPattern)>,
+    company_hits: Vec<usize>,
+    risk_classes: Vec<String>,
+    include_unknown_risk_class: bool,
+    /// Normalized risk classes seen (empty key: none), for the report.
+    risk_class_counts: BTreeMap<String, usize>,
+    total: usize,
+    kept: usize,
+}
+
+impl ItemFilter {
+    pub fn new(opts: &FilterOptions) -> Result<Self, String> {
+        let companies = opts
+            .companies
+            .iter()
+            .map(|p| Ok((p.clone(), CompanyPattern::parse(p)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(ItemFilter {
+            statuses: opts
+                .statuses
+                .iter()
+                .map(|s| s.trim().to_lowercase())
+                .collect(),
+            company_hits: vec![0; companies.len()],
+            companies,
+            risk_classes: opts
+                .risk_classes
+                .iter()
+                .map(|s| normalize_risk_class(s))
+                .collect(),
+            include_unknown_risk_class: opts.include_unknown_risk_class,
+            risk_class_counts: BTreeMap::new(),
+            total: 0,
+            kept: 0,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.statuses.is_empty() || !self.companies.is_empty() || !self.risk_classes.is_empty()
+    }
+
+    /// Whether `item` passes the filter.
+    pub fn keep(&mut self, item: &Value) -> bool {
+        self.total += 1;
+        let status_ok = self.statuses.is_empty() || {
+            let status = STATUS_KEYS
+                .iter()
+                .find_map(|k| item.get(*k).filter(|v| !v.is_null()))
+                .map(|v| value_to_string(v).to_lowercase())
+                .unwrap_or_default();
+            self.statuses.contains(&status)
+        };
+        let mut company_ok = self.companies.is_empty();
+        if !self.companies.is_empty() {
+            let company = get_field(item, "companyName");
+            for ((_, pattern), hits) in self.companies.iter().zip(&mut self.company_hits) {
+                if pattern.is_match(&company) {
+                    *hits += 1;
+                    company_ok = true;
+                }
+            }
+        }
+        let risk_class_ok = self.risk_classes.is_empty() || {
+            let class = normalize_risk_class(&get_field(item, "riskClass"));
+            let ok = if class.is_empty() {
+                self.include_unknown_risk_class
+            } else {
+                self.risk_classes.contains(&class)
+            };
+            *self.risk_class_counts.entry(class).or_default() += 1;
+            ok
+        };
+        let keep = status_ok && company_ok && risk_class_ok;
+        if keep {
+            self.kept += 1;
+        }
+        keep
+    }
+
+    /// Print the "kept X of Y" summary (with the matches per company
+    /// pattern, so a typo shows up as 0, and the risk classes seen) if any
+    /// filter is set.
+    pub fn report(&self) {
+        if !self.is_active() {
+            return;
+        }
+        if !self.statuses.is_empty() {
+            info!("Status filter: {}", self.statuses.join(", "));
+        }
+        for ((pattern, _), hits) in self.companies.iter().zip(&self.company_hits) {
+            info!("Company filter '{}': {} items match", pattern, hits);
+        }
+        if !self.risk_classes.is_empty() {
+            info!("Risk class filter: {}", self.risk_classes.join(", "));
+            let seen: Vec<String> = self
+                .risk_class_counts
+                .iter()
+                .map(|(class, n)| {
+                    let class = if class.is_empty() { "(unknown)" } else { class };
+                    format!("{} {}", class, n)
+                })
+                .collect();
+            info!("  risk classes seen: {}", seen.join(", "));
+        }
+        info!("Filter: kept {} of {} items.", self.kept, self.total);
+    }
+}
+
+/// Tracks input items the row builders can't use as intended: non-objects
+/// are skipped, and a `udiDis` or `tradeNames` that isn't an array is
+/// ignored. With `strict`, the first such item is an error instead.
+pub struct ShapeCheck {
+    strict: bool,
+    index: usize,
+    skipped: usize,
+    malformed: usize,
+}
+
+impl ShapeCheck {
+    pub fn new(strict: bool) -> Self {
+        ShapeCheck {
+            strict,
+            index: 0,
+            skipped: 0,
+            malformed: 0,
+        }
+    }
+
+    /// Check the next input item (items are numbered from 0 in input order).
+    pub fn check(&mut self, item: &Value) -> Result<(), String> {
+        let index = self.index;
+        self.index += 1;
+        let Some(problem) = shape_problem(item) else {
+            return Ok(());
+        };
+        if self.strict {
+            return Err(format!(
+                "Item {}: {} (--strict): {}",
+                index,
+                problem,
+                snippet(item, 120)
+            ));
+        }
+        if item.is_object() {
+            self.malformed += 1;
+        } else {
+            self.skipped += 1;
+        }
+        Ok(())
+    }
+
+    /// Warn about what lenient mode let through.
+    pub fn report(&self) {
+        if self.skipped > 0 {
+            warn!(
+                "skipped {} non-object entries (use --strict to fail instead).",
+                self.skipped
+            );
+        }
+        if self.malformed > 0 {
+            warn!(
+                "{} items have a udiDis/tradeNames that is not an array; those parts were ignored.",
+                self.malformed
+            );
+        }
+    }
+}
+
+/// Describe why `item` doesn't have the expected UDI item shape.
+fn shape_problem(item: &Value) -> Option<String> {
+    if !item.is_object() {
+        return Some("entry is not an object".to_string());
+    }
+    let udi_dis = item.get("udiDis")?;
+    let Some(arr) = udi_dis.as_array() else {
+        return Some("udiDis is not an array".to_string());
+    };
+    arr.iter()
+        .enumerate()
+        .find_map(|(i, udi)| match udi.get("tradeNames") {
+            Some(tn) if !tn.is_array() => Some(format!("udiDis[{}].tradeNames is not an array", i)),
+            _ => None,
+        })
+}
+
+/// Compact JSON of `value`, cut to at most `max` characters.
+fn snippet(value: &Value, max: usize) -> String {
+    let s = value.to_string();
+    match s.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &s[..cut]),
+        None => s,
+    }
+}
+
 // --- Header collection and row building ---
 
+/// Language of a tradeNames entry (`ANY` when unspecified).
+pub fn trade_name_lang(tn: &Value) -> String {
+    tn.get("language")
+        .or_else(|| tn.get("lang"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| "ANY".to_string())
+}
+
 /// Scan all udiDis -> tradeNames arrays to discover which languages exist,
 /// returned in a stable sorted order.
 pub fn collect_trade_name_languages(values: &[Value]) -> Vec<String> {
@@ -117,13 +640,7 @@ pub fn collect_trade_name_languages(values: &[Value]) -> Vec<String> {
             for udi in udi_arr {
                 if let Some(tn_arr) = udi.get("tradeNames").and_then(|v| v.as_array()) {
                     for tn in tn_arr {
-                        let lang = tn
-                            .get("language")
-                            .or_else(|| tn.get("lang"))
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.trim().to_string())
-                            .unwrap_or_else(|| "ANY".to_string());
-                        langs.insert(lang);
+                        langs.insert(trade_name_lang(tn));
                     }
                 }
             }
@@ -133,118 +650,674 @@ pub fn collect_trade_name_languages(values: &[Value]) -> Vec<String> {
     langs.into_iter().collect()
 }
 
+/// Only scalar udiDis values turn a field into a `udi_<field>` column.
+fn is_udi_column_value(val: &Value) -> bool {
+    !matches!(val, Value::Array(_) | Value::Object(_))
+}
+
+/// Collect the main columns in the order fields first appear in the source
+/// JSON (serde_json is built with `preserve_order`), followed by `udiDiCode`,
+/// the `udi_<field>` columns and one `tradeName_<LANG>` column per language.
 pub fn collect_headers(values: &[Value]) -> (Vec<String>, Vec<String>) {
-    let mut seen = BTreeSet::new();
-    let mut headers: Vec<String> = Vec::new();
+    collect_headers_with(values, &ValueFormat::DEFAULT)
+}
 
-    for item in values {
-        if let Value::Object(map) = item {
-            for key in map.keys() {
-                if key == "udiDis" {
-                    continue;
+/// [`collect_headers`] with object-valued fields flattened to
+/// `fmt.flatten_depth` levels and, with `fmt.split_lang_arrays`,
+/// language-tagged arrays split into per-language columns. A non-empty
+/// `fmt.lang_priority` appends `tradeName_preferred`.
+pub fn collect_headers_with(values: &[Value], fmt: &ValueFormat) -> (Vec<String>, Vec<String>) {
+    let builder = RowBuilder::from_values(values, *fmt);
+    (builder.headers(), builder.trade_name_languages())
+}
+
+/// The fields of an item with non-empty object values expanded into
+/// `parent.child` entries, up to `depth` levels; deeper objects (and empty
+/// ones) stay single fields rendered as JSON.
+fn flattened_fields(map: &serde_json::Map<String, Value>, depth: usize) -> Vec<(String, &Value)> {
+    fn walk<'v>(
+        prefix: &str,
+        map: &'v serde_json::Map<String, Value>,
+        depth: usize,
+        out: &mut Vec<(String, &'v Value)>,
+    ) {
+        for (key, val) in map {
+            let name = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match val {
+                Value::Object(child) if depth > 0 && !child.is_empty() => {
+                    walk(&name, child, depth - 1, out)
                 }
-                if seen.insert(key.clone()) {
-                    headers.push(key.clone());
+                _ => out.push((name, val)),
+            }
+        }
+    }
+    let mut out = Vec::with_capacity(map.len());
+    walk("", map, depth, &mut out);
+    out
+}
+
+/// Main-column cells of an item (everything but `udiDis`), rendered with
+/// `fmt`. Object fields are flattened per [`flattened_fields`]; with
+/// `fmt.split_lang_arrays` a language-tagged array becomes one
+/// `<field>_<LANG>` cell per language with text, languages sorted.
+fn main_cells(map: &serde_json::Map<String, Value>, fmt: &ValueFormat) -> Vec<(String, String)> {
+    let mut cells = Vec::with_capacity(map.len());
+    for (key, val) in flattened_fields(map, fmt.flatten_depth) {
+        if key == "udiDis" {
+            continue;
+        }
+        match val {
+            Value::Array(arr) if fmt.split_lang_arrays && is_lang_tagged(arr) => {
+                let by_lang: BTreeMap<String, String> =
+                    texts_by_lang(arr, fmt).into_iter().collect();
+                for (lang, text) in by_lang {
+                    cells.push((format!("{}_{}", key, lang), text));
                 }
             }
+            _ => {
+                let cell = value_to_string_with(val, fmt);
+                cells.push((key, cell));
+            }
         }
     }
+    cells
+}
+
+/// True for a non-empty array whose elements are all objects carrying a
+/// `language` (or `lang`) string, like `tradeNames`.
+fn is_lang_tagged(arr: &[Value]) -> bool {
+    !arr.is_empty()
+        && arr.iter().all(|el| {
+            el.get("language")
+                .or_else(|| el.get("lang"))
+                .is_some_and(|v| v.is_string())
+        })
+}
+
+/// Texts of a language-tagged array keyed by language; repeated languages
+/// are joined with `fmt.array_sep` and entries without text are skipped.
+fn texts_by_lang(arr: &[Value], fmt: &ValueFormat) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+
+    for el in arr {
+        let lang = trade_name_lang(el);
 
-    let trade_name_langs = collect_trade_name_languages(values);
+        let text = el
+            .get("textValue")
+            .or_else(|| el.get("value"))
+            .or_else(|| el.get("name"))
+            .and_then(|v| v.as_str())
+            .map(|s| clean_text(s, fmt))
+            .map(|s| match fmt.newline_token {
+                Some(token) => escape_newlines(&s, token),
+                None => s,
+            })
+            .unwrap_or_default();
 
-    // Append udiDiCode, then one column per language
-    headers.push("udiDiCode".to_string());
-    for lang in &trade_name_langs {
-        headers.push(format!("tradeName_{}", lang));
+        if !text.is_empty() {
+            map.entry(lang)
+                .and_modify(|existing: &mut String| {
+                    existing.push_str(fmt.array_sep);
+                    existing.push_str(&text);
+                })
+                .or_insert(text);
+        }
     }
 
-    (headers, trade_name_langs)
+    map
 }
 
-/// Extract per-language trade names from a single udiDis entry.
-fn extract_trade_names_by_lang(udi: &Value) -> HashMap<String, String> {
-    let mut map = HashMap::new();
+/// Extract per-language trade names from a single udiDis entry, limited
+/// to the `fmt.langs` selection.
+fn extract_trade_names_by_lang(udi: &Value, fmt: &ValueFormat) -> HashMap<String, String> {
+    let mut names = udi
+        .get("tradeNames")
+        .and_then(|v| v.as_array())
+        .map(|arr| texts_by_lang(arr, fmt))
+        .unwrap_or_default();
+    names.retain(|lang, _| fmt.keeps_lang(lang));
+    names
+}
 
-    if let Some(tn_arr) = udi.get("tradeNames").and_then(|v| v.as_array()) {
-        for tn in tn_arr {
-            let lang = tn
-                .get("language")
-                .or_else(|| tn.get("lang"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.trim().to_string())
-                .unwrap_or_else(|| "ANY".to_string());
+/// Narrow the headers from `collect_headers` to the `--columns` selection
+/// (in the order given, with `udiDiCode` and the `tradeName_*` columns
+/// appended unless listed explicitly) minus any `--exclude-columns`.
+/// Unknown column names are an error so typos don't silently drop data.
+pub fn select_columns(
+    headers: Vec<String>,
+    columns: &[String],
+    exclude: &[String],
+) -> Result<Vec<String>, String> {
+    let check = |name: &String, flag: &str| {
+        if headers.contains(name) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unknown column '{}' in {} (available: {})",
+                name,
+                flag,
+                headers.join(", ")
+            ))
+        }
+    };
+    for name in columns {
+        check(name, "--columns")?;
+    }
+    for name in exclude {
+        check(name, "--exclude-columns")?;
+    }
 
-            let text = tn
-                .get("textValue")
-                .or_else(|| tn.get("value"))
-                .or_else(|| tn.get("name"))
-                .and_then(|v| v.as_str())
-                .map(|s| sanitize(s.trim()))
-                .unwrap_or_default();
+    let mut selected: Vec<String> = if columns.is_empty() {
+        headers.clone()
+    } else {
+        let mut seen = HashSet::new();
+        let mut sel: Vec<String> = columns
+            .iter()
+            .filter(|c| seen.insert(c.as_str()))
+            .cloned()
+            .collect();
+        for h in &headers {
+            if (h == "udiDiCode" || h.starts_with("tradeName_")) && !seen.contains(h.as_str()) {
+                sel.push(h.clone());
+            }
+        }
+        sel
+    };
+    selected.retain(|h| !exclude.contains(h));
 
-            if !text.is_empty() {
-                map.entry(lang)
-                    .and_modify(|existing: &mut String| {
-                        existing.push_str(" | ");
-                        existing.push_str(&text);
-                    })
-                    .or_insert(text);
+    if selected.is_empty() {
+        return Err("No columns left after applying --columns/--exclude-columns".into());
+    }
+    Ok(selected)
+}
+
+// --- Column schema (--schema / --write-schema) ---
+
+/// Read a `--schema` file: a JSON array of column names, or plain text with
+/// one name per line (blank lines and `#` comments ignored).
+pub fn read_schema(path: &std::path::Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read schema {}: {}", path.display(), e))?;
+    let names: Vec<String> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid JSON schema {}: {}", path.display(), e))?
+    } else {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect()
+    };
+
+    let mut seen = HashSet::new();
+    if let Some(dup) = names.iter().find(|n| !seen.insert(n.as_str())) {
+        return Err(format!("Duplicate column '{}' in schema {}", dup, path.display()).into());
+    }
+    if names.is_empty() {
+        return Err(format!("Schema {} lists no columns", path.display()).into());
+    }
+    Ok(names)
+}
+
+/// Write `headers` as a `--schema` file, one column name per line.
+pub fn write_schema(headers: &[String], path: &std::path::Path) -> std::io::Result<()> {
+    let mut content = headers.join("\n");
+    content.push('\n');
+    std::fs::write(path, content)
+}
+
+/// Order `headers` by `schema`: schema columns come first in schema order
+/// (columns the data lacks stay in and are filled with empty cells), data
+/// columns missing from the schema are appended with a warning, or dropped
+/// when `strict`.
+pub fn apply_schema(headers: Vec<String>, schema: &[String], strict: bool) -> Vec<String> {
+    let known: HashSet<&str> = schema.iter().map(String::as_str).collect();
+    let extra: Vec<String> = headers
+        .into_iter()
+        .filter(|h| !known.contains(h.as_str()))
+        .collect();
+
+    let mut result = schema.to_vec();
+    if !extra.is_empty() {
+        if strict {
+            info!(
+                "Dropping {} column(s) not in the schema: {}",
+                extra.len(),
+                extra.join(", ")
+            );
+        } else {
+            warn!(
+                "Appending {} column(s) not in the schema: {}",
+                extra.len(),
+                extra.join(", ")
+            );
+            result.extend(extra);
+        }
+    }
+    result
+}
+
+/// Columns of `current` missing from `previous` (added) and columns of
+/// `previous` missing from `current` (removed), each in its list's order,
+/// for `--schema-snapshot`.
+pub fn schema_changes(previous: &[String], current: &[String]) -> (Vec<String>, Vec<String>) {
+    let missing_from = |list: &[String], other: &[String]| -> Vec<String> {
+        let known: HashSet<&str> = other.iter().map(String::as_str).collect();
+        list.iter()
+            .filter(|h| !known.contains(h.as_str()))
+            .cloned()
+            .collect()
+    };
+    (
+        missing_from(current, previous),
+        missing_from(previous, current),
+    )
+}
+
+/// Compare `discovered` with the `--schema-snapshot` file at `path` and log
+/// the [`schema_changes`]; with `fail` a change is an error instead. A
+/// missing file is no change. The caller stores the new list with
+/// [`write_schema`] once the run's outputs are written.
+pub fn check_schema_snapshot(
+    path: &std::path::Path,
+    discovered: &[String],
+    fail: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !path.exists() {
+        info!("No schema snapshot at {} yet", path.display());
+        return Ok(());
+    }
+    let previous = read_schema(path)?;
+    let (added, removed) = schema_changes(&previous, discovered);
+    if added.is_empty() && removed.is_empty() {
+        info!("Schema unchanged since {}", path.display());
+        return Ok(());
+    }
+    let msg = format!(
+        "Schema changed since {}: {} column(s) added [{}], {} removed [{}]",
+        path.display(),
+        added.len(),
+        added.join(", "),
+        removed.len(),
+        removed.join(", ")
+    );
+    if fail {
+        return Err(msg.into());
+    }
+    warn!("{}", msg);
+    Ok(())
+}
+
+// --- Trade-name layout (--trade-names) ---
+
+/// How trade names are laid out in the UDI export.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TradeNameLayout {
+    /// One `tradeName_<LANG>` column per language
+    #[default]
+    Wide,
+    /// `tradeName_language` + `tradeName_text`, one row per language
+    Long,
+}
+
+pub const TRADE_NAME_LANGUAGE: &str = "tradeName_language";
+pub const TRADE_NAME_TEXT: &str = "tradeName_text";
+
+/// Turn wide rows into the long trade-name layout: the `tradeName_<LANG>`
+/// columns (not `tradeName_preferred`) are replaced, at the position of the
+/// first one, by `tradeName_language` and `tradeName_text`, and each row
+/// becomes one row per language with a non-empty name. Rows without any
+/// trade name are kept once with both columns empty.
+pub fn trade_names_long(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let langs: Vec<(usize, &str)> = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| h.as_str() != PREFERRED_TRADE_NAME)
+        .filter_map(|(i, h)| h.strip_prefix("tradeName_").map(|lang| (i, lang)))
+        .collect();
+    let Some(&(first, _)) = langs.first() else {
+        return (headers.to_vec(), rows);
+    };
+    let is_lang = |i: usize| langs.iter().any(|&(j, _)| j == i);
+
+    let mut long_headers = Vec::with_capacity(headers.len() + 2 - langs.len());
+    for (i, h) in headers.iter().enumerate() {
+        if i == first {
+            long_headers.push(TRADE_NAME_LANGUAGE.to_string());
+            long_headers.push(TRADE_NAME_TEXT.to_string());
+        } else if !is_lang(i) {
+            long_headers.push(h.clone());
+        }
+    }
+
+    let mut long_rows = Vec::with_capacity(rows.len());
+    for row in rows {
+        let names: Vec<(&str, &str)> = langs
+            .iter()
+            .filter_map(|&(i, lang)| {
+                let text = row.get(i).map_or("", String::as_str);
+                (!text.is_empty()).then_some((lang, text))
+            })
+            .collect();
+        let names = if names.is_empty() {
+            vec![("", "")]
+        } else {
+            names
+        };
+        for (lang, text) in names {
+            let mut out = Vec::with_capacity(long_headers.len());
+            for (i, cell) in row.iter().enumerate() {
+                if i == first {
+                    out.push(lang.to_string());
+                    out.push(text.to_string());
+                } else if !is_lang(i) {
+                    out.push(cell.clone());
+                }
             }
+            long_rows.push(out);
         }
     }
+    (long_headers, long_rows)
+}
 
-    map
+/// Where a header's value comes from when building UDI rows.
+enum Column<'a> {
+    Field(&'a str),
+    UdiDiCode,
+    UdiField(&'a str),
+    TradeName(&'a str),
+    PreferredTradeName,
 }
 
+fn classify_columns<'h>(headers: &'h [String], trade_name_langs: &[String]) -> Vec<Column<'h>> {
+    headers
+        .iter()
+        .map(|h| {
+            if h == "udiDiCode" {
+                return Column::UdiDiCode;
+            }
+            if h == PREFERRED_TRADE_NAME {
+                return Column::PreferredTradeName;
+            }
+            if let Some(field) = h.strip_prefix("udi_") {
+                return Column::UdiField(field);
+            }
+            match h.strip_prefix("tradeName_") {
+                Some(lang) if trade_name_langs.iter().any(|l| l == lang) => Column::TradeName(lang),
+                _ => Column::Field(h),
+            }
+        })
+        .collect()
+}
+
+/// Build one row per udiDis entry. `headers` may be any subset or order of
+/// the columns returned by `collect_headers` (see `select_columns`).
 pub fn build_rows(
     values: &[Value],
     headers: &[String],
     trade_name_langs: &[String],
 ) -> Vec<Vec<String>> {
-    let main_header_count = headers.len() - 1 - trade_name_langs.len();
-    let mut rows = Vec::new();
+    build_rows_with(values, headers, trade_name_langs, &ValueFormat::DEFAULT)
+}
 
-    for item in values {
-        if !item.is_object() {
-            continue;
+pub fn build_rows_with(
+    values: &[Value],
+    headers: &[String],
+    trade_name_langs: &[String],
+    fmt: &ValueFormat,
+) -> Vec<Vec<String>> {
+    RowBuilder::from_values(values, *fmt).rows_for(headers, trade_name_langs)
+}
+
+// --- Incremental row building (streamed input) ---
+
+/// Incremental row building for streamed input: items are pushed one at a
+/// time and only their flattened cells are kept, so the source document
+/// never sits in memory as a whole. `collect_headers_with` and
+/// `build_rows_with` run on top of it for input that is already loaded.
+pub struct RowBuilder<'a> {
+    fmt: ValueFormat<'a>,
+    items: usize,
+    /// Main columns in first-seen order.
+    fields: Vec<String>,
+    field_idx: HashMap<String, usize>,
+    /// Every udiDis key seen (interned). `udi_columns` lists the ones that
+    /// became `udi_<field>` columns, in first-seen order.
+    udi_keys: Vec<String>,
+    udi_key_idx: HashMap<String, usize>,
+    udi_columns: Vec<usize>,
+    langs: BTreeSet<String>,
+    /// Non-empty main-field cells per item, shared by that item's rows.
+    item_cells: Vec<Vec<(usize, String)>>,
+    rows: Vec<PendingRow>,
+}
+
+/// One future output row: an item plus one of its udiDis entries.
+struct PendingRow {
+    item: usize,
+    code: String,
+    udi_cells: Vec<(usize, String)>,
+    trade_names: HashMap<String, String>,
+}
+
+fn intern(names: &mut Vec<String>, idx: &mut HashMap<String, usize>, name: &str) -> usize {
+    if let Some(&i) = idx.get(name) {
+        return i;
+    }
+    names.push(name.to_string());
+    idx.insert(name.to_string(), names.len() - 1);
+    names.len() - 1
+}
+
+impl<'a> RowBuilder<'a> {
+    pub fn new(fmt: ValueFormat<'a>) -> Self {
+        RowBuilder {
+            fmt,
+            items: 0,
+            fields: Vec::new(),
+            field_idx: HashMap::new(),
+            udi_keys: Vec::new(),
+            udi_key_idx: HashMap::new(),
+            udi_columns: Vec::new(),
+            langs: BTreeSet::new(),
+            item_cells: Vec::new(),
+            rows: Vec::new(),
         }
+    }
 
-        let main_fields: Vec<String> = headers[..main_header_count]
-            .iter()
-            .map(|key| get_field(item, key))
+    /// A builder with every item of `values` pushed.
+    pub fn from_values(values: &[Value], fmt: ValueFormat<'a>) -> Self {
+        let mut builder = RowBuilder::new(fmt);
+        for item in values {
+            builder.push(item);
+        }
+        builder
+    }
+
+    /// Number of items pushed so far.
+    pub fn item_count(&self) -> usize {
+        self.items
+    }
+
+    /// Add one item; non-objects are counted but produce no rows.
+    pub fn push(&mut self, item: &Value) {
+        self.items += 1;
+        let Value::Object(map) = item else {
+            return;
+        };
+
+        let mut cells = Vec::new();
+        for (key, cell) in main_cells(map, &self.fmt) {
+            let i = intern(&mut self.fields, &mut self.field_idx, &key);
+            if !cell.is_empty() {
+                cells.push((i, cell));
+            }
+        }
+        let item_idx = self.item_cells.len();
+        self.item_cells.push(cells);
+
+        match map.get("udiDis").and_then(|v| v.as_array()) {
+            Some(arr) => {
+                for udi in arr {
+                    self.push_udi(item_idx, udi);
+                }
+            }
+            None => self.rows.push(PendingRow {
+                item: item_idx,
+                code: String::new(),
+                udi_cells: Vec::new(),
+                trade_names: HashMap::new(),
+            }),
+        }
+    }
+
+    fn push_udi(&mut self, item: usize, udi: &Value) {
+        let mut udi_cells = Vec::new();
+        if let Value::Object(map) = udi {
+            for (key, val) in map {
+                if key == "udiDiCode" || key == "tradeNames" {
+                    continue;
+                }
+                let i = intern(&mut self.udi_keys, &mut self.udi_key_idx, key);
+                if is_udi_column_value(val) && !self.udi_columns.contains(&i) {
+                    self.udi_columns.push(i);
+                }
+                let cell = value_to_string_with(val, &self.fmt);
+                if !cell.is_empty() {
+                    udi_cells.push((i, cell));
+                }
+            }
+        }
+        if let Some(tn_arr) = udi.get("tradeNames").and_then(|v| v.as_array()) {
+            for tn in tn_arr {
+                let lang = trade_name_lang(tn);
+                if self.fmt.keeps_lang(&lang) {
+                    self.langs.insert(lang);
+                }
+            }
+        }
+
+        self.rows.push(PendingRow {
+            item,
+            code: get_field_with(udi, "udiDiCode", &self.fmt),
+            udi_cells,
+            trade_names: extract_trade_names_by_lang(udi, &self.fmt),
+        });
+    }
+
+    /// Trade-name languages seen so far, sorted.
+    pub fn trade_name_languages(&self) -> Vec<String> {
+        self.langs.iter().cloned().collect()
+    }
+
+    /// The main columns in first-seen order, then `udiDiCode`, the
+    /// `udi_<field>` columns, one `tradeName_<LANG>` column per language and
+    /// `tradeName_preferred` when a language priority is set.
+    pub fn headers(&self) -> Vec<String> {
+        let mut headers = self.fields.clone();
+        headers.push("udiDiCode".to_string());
+        for &i in &self.udi_columns {
+            headers.push(format!("udi_{}", self.udi_keys[i]));
+        }
+        for lang in &self.langs {
+            headers.push(format!("tradeName_{}", lang));
+        }
+        if !self.fmt.lang_priority.is_empty() {
+            headers.push(PREFERRED_TRADE_NAME.to_string());
+        }
+        headers
+    }
+
+    /// Materialize the rows for `headers` (any subset or order of
+    /// [`RowBuilder::headers`], see `select_columns`).
+    pub fn into_rows(self, headers: &[String]) -> Vec<Vec<String>> {
+        self.rows_for(headers, &self.trade_name_languages())
+    }
+
+    /// Rows for `headers`, with `tradeName_<LANG>` columns recognized for
+    /// the `trade_name_langs` given (see `build_rows_with`).
+    fn rows_for(&self, headers: &[String], trade_name_langs: &[String]) -> Vec<Vec<String>> {
+        enum Source<'h> {
+            Field(usize),
+            UdiDiCode,
+            UdiField(usize),
+            TradeName(&'h str),
+            PreferredTradeName,
+            Empty,
+        }
+        let sources: Vec<Source> = classify_columns(headers, trade_name_langs)
+            .into_iter()
+            .map(|col| match col {
+                Column::Field(key) => self
+                    .field_idx
+                    .get(key)
+                    .map_or(Source::Empty, |&i| Source::Field(i)),
+                Column::UdiDiCode => Source::UdiDiCode,
+                Column::UdiField(key) => self
+                    .udi_key_idx
+                    .get(key)
+                    .map_or(Source::Empty, |&i| Source::UdiField(i)),
+                Column::TradeName(lang) => Source::TradeName(lang),
+                Column::PreferredTradeName => Source::PreferredTradeName,
+            })
             .collect();
 
-        let udi_entries: Vec<(String, HashMap<String, String>)> = item
-            .get("udiDis")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .map(|udi| {
-                        let code = get_field(udi, "udiDiCode");
-                        let tn_map = extract_trade_names_by_lang(udi);
-                        (code, tn_map)
+        let mut main: Vec<&str> = vec![""; self.fields.len()];
+        let mut udi: Vec<&str> = vec![""; self.udi_keys.len()];
+        let mut current_item = usize::MAX;
+        let mut rows = Vec::with_capacity(self.rows.len());
+
+        for row in &self.rows {
+            if row.item != current_item {
+                main.iter_mut().for_each(|c| *c = "");
+                for (i, cell) in &self.item_cells[row.item] {
+                    main[*i] = cell;
+                }
+                current_item = row.item;
+            }
+            for (i, cell) in &row.udi_cells {
+                udi[*i] = cell;
+            }
+
+            rows.push(
+                sources
+                    .iter()
+                    .map(|src| match src {
+                        Source::Field(i) => main[*i].to_string(),
+                        Source::UdiDiCode => row.code.clone(),
+                        Source::UdiField(i) => udi[*i].to_string(),
+                        Source::TradeName(lang) => {
+                            row.trade_names.get(*lang).cloned().unwrap_or_default()
+                        }
+                        Source::PreferredTradeName => {
+                            preferred_trade_name(&row.trade_names, self.fmt.lang_priority)
+                        }
+                        Source::Empty => String::new(),
                     })
-                    .collect()
-            })
-            .unwrap_or_else(|| vec![(String::new(), HashMap::new())]);
+                    .collect(),
+            );
 
-        for (code, tn_map) in &udi_entries {
-            let mut row = main_fields.clone();
-            row.push(code.clone());
-            for lang in trade_name_langs {
-                row.push(tn_map.get(lang).cloned().unwrap_or_default());
+            for (i, _) in &row.udi_cells {
+                udi[*i] = "";
             }
-            rows.push(row);
         }
-    }
 
-    rows
+        rows
+    }
 }
 
 // --- Flat data processing (actors, mandates) ---
 
 pub fn collect_flat_headers(values: &[Value]) -> Vec<String> {
-    let mut seen = BTreeSet::new();
+    let mut seen = HashSet::new();
     let mut headers: Vec<String> = Vec::new();
 
     for item in values {
@@ -261,10 +1334,23 @@ pub fn collect_flat_headers(values: &[Value]) -> Vec<String> {
 }
 
 pub fn build_flat_rows(values: &[Value], headers: &[String]) -> Vec<Vec<String>> {
+    build_flat_rows_with(values, headers, &ValueFormat::DEFAULT)
+}
+
+pub fn build_flat_rows_with(
+    values: &[Value],
+    headers: &[String],
+    fmt: &ValueFormat,
+) -> Vec<Vec<String>> {
     values
         .iter()
         .filter(|item| item.is_object())
-        .map(|item| headers.iter().map(|key| get_field(item, key)).collect())
+        .map(|item| {
+            headers
+                .iter()
+                .map(|key| get_field_with(item, key, fmt))
+                .collect()
+        })
         .collect()
 }
 
@@ -300,7 +1386,7 @@ pub fn fetch_mandate_details(
 
     for (i, id) in mandate_ids.iter().enumerate() {
         if (i + 1) % 50 == 0 || i + 1 == total {
-            eprintln!("[mandate-details] Fetching {}/{} ...", i + 1, total);
+            info!("[mandate-details] Fetching {}/{} ...", i + 1, total);
         }
 
         let url = format!("https://swissdamed.ch/public/act/mandates/{}", id);
@@ -313,8 +1399,8 @@ pub fn fetch_mandate_details(
             let body: Value = resp.json()?;
             details.push(body);
         } else {
-            eprintln!(
-                "[mandate-details] Warning: HTTP {} for mandate {}",
+            warn!(
+                "[mandate-details] HTTP {} for mandate {}",
                 resp.status(),
                 id
             );
@@ -322,6 +1408,639 @@ pub fn fetch_mandate_details(
         }
     }
 
-    eprintln!("[mandate-details] Fetched {} details.", details.len());
+    info!("[mandate-details] Fetched {} details.", details.len());
     Ok(details)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_values() -> Vec<Value> {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/udi_sample.json");
+        crate::download::load_json_file(&path).expect("load fixture")
+    }
+
+    /// Columns follow the field order of the source JSON, identically on
+    /// every run.
+    #[test]
+    fn header_order_follows_source() {
+        let values = sample_values();
+        let (first, langs) = collect_headers(&values);
+        let (second, _) = collect_headers(&values);
+        assert_eq!(first, second);
+        assert_eq!(
+            first,
+            [
+                "basicUdi",
+                "companyName",
+                "deviceName",
+                "modelName",
+                "riskClass",
+                "deviceType",
+                "udiDiCode",
+                "tradeName_DE",
+                "tradeName_EN",
+                "tradeName_FR",
+                "tradeName_IT",
+            ]
+        );
+        assert_eq!(langs, ["DE", "EN", "FR", "IT"]);
+    }
+
+    /// `--langs` narrows the trade-name columns identically in the two-pass
+    /// and the streaming conversion.
+    #[test]
+    fn langs_filter_trade_name_columns() {
+        let values = sample_values();
+        let langs = ["de".to_string(), "IT".to_string()];
+        let fmt = ValueFormat {
+            langs: &langs,
+            ..ValueFormat::DEFAULT
+        };
+        let (headers, trade_name_langs) = collect_headers_with(&values, &fmt);
+        assert_eq!(trade_name_langs, ["DE", "IT"]);
+        assert_eq!(
+            headers
+                .iter()
+                .filter(|h| h.starts_with("tradeName_"))
+                .collect::<Vec<_>>(),
+            ["tradeName_DE", "tradeName_IT"]
+        );
+
+        let mut builder = RowBuilder::new(fmt);
+        for item in &values {
+            builder.push(item);
+        }
+        assert_eq!(builder.headers(), headers);
+        assert_eq!(
+            builder.into_rows(&headers),
+            build_rows_with(&values, &headers, &trade_name_langs, &fmt)
+        );
+    }
+
+    /// Trailing zeros are trimmed from the fraction only, so whole numbers
+    /// keep theirs at `--float-precision 0`.
+    #[test]
+    fn float_precision_trims_fraction_only() {
+        assert_eq!(format_float(120.0, Some(0)), "120");
+        assert_eq!(format_float(119.6, Some(0)), "120");
+        assert_eq!(format_float(120.5, Some(2)), "120.5");
+        assert_eq!(format_float(100.0, Some(3)), "100");
+        assert_eq!(format_float(0.1 + 0.2, Some(10)), "0.3");
+        assert_eq!(format_float(0.1 + 0.2, None), "0.30000000000000004");
+    }
+
+    /// `--lang-priority` adds `tradeName_preferred` on the two-pass path
+    /// exactly like in the streaming conversion.
+    #[test]
+    fn lang_priority_adds_preferred_column() {
+        let values = sample_values();
+        let priority = ["it".to_string(), "FR".to_string()];
+        let fmt = ValueFormat {
+            lang_priority: &priority,
+            ..ValueFormat::DEFAULT
+        };
+        let (headers, trade_name_langs) = collect_headers_with(&values, &fmt);
+        assert_eq!(headers.last().unwrap(), PREFERRED_TRADE_NAME);
+        let rows = build_rows_with(&values, &headers, &trade_name_langs, &fmt);
+        let it = headers.iter().position(|h| h == "tradeName_IT").unwrap();
+        let fr = headers.iter().position(|h| h == "tradeName_FR").unwrap();
+        assert!(rows.iter().any(|row| !row.last().unwrap().is_empty()));
+        for row in &rows {
+            let expected = if row[it].is_empty() {
+                &row[fr]
+            } else {
+                &row[it]
+            };
+            assert_eq!(row.last().unwrap(), expected);
+        }
+
+        let mut builder = RowBuilder::new(fmt);
+        for item in &values {
+            builder.push(item);
+        }
+        assert_eq!(builder.headers(), headers);
+        assert_eq!(builder.into_rows(&headers), rows);
+    }
+
+    /// `--trade-names long` puts the language/text pair where the first
+    /// `tradeName_<LANG>` column was, emits one row per non-empty name,
+    /// keeps rows without names once and leaves `tradeName_preferred` alone.
+    #[test]
+    fn trade_names_long_splits_rows_per_language() {
+        let strings = |cells: &[&str]| cells.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let headers = strings(&[
+            "udiDiCode",
+            "tradeName_DE",
+            "tradeName_FR",
+            "riskClass",
+            PREFERRED_TRADE_NAME,
+        ]);
+        let rows = vec![
+            strings(&["1", "Pflaster", "Pansement", "I", "Pflaster"]),
+            strings(&["2", "", "Seringue", "IIa", "Seringue"]),
+            strings(&["3", "", "", "III", ""]),
+        ];
+        let (long_headers, long_rows) = trade_names_long(&headers, rows);
+        assert_eq!(
+            long_headers,
+            [
+                "udiDiCode",
+                TRADE_NAME_LANGUAGE,
+                TRADE_NAME_TEXT,
+                "riskClass",
+                PREFERRED_TRADE_NAME,
+            ]
+        );
+        assert_eq!(
+            long_rows,
+            [
+                strings(&["1", "DE", "Pflaster", "I", "Pflaster"]),
+                strings(&["1", "FR", "Pansement", "I", "Pflaster"]),
+                strings(&["2", "FR", "Seringue", "IIa", "Seringue"]),
+                strings(&["3", "", "", "III", ""]),
+            ]
+        );
+
+        let plain = strings(&["udiDiCode", "riskClass"]);
+        let unchanged = vec![strings(&["1", "I"])];
+        assert_eq!(
+            trade_names_long(&plain, unchanged.clone()),
+            (plain, unchanged)
+        );
+    }
+
+    /// `--schema` puts the schema columns first (empty where the data has
+    /// none) and appends the other data columns, or drops them when strict.
+    #[test]
+    fn apply_schema_orders_and_fills_columns() {
+        let values = sample_values();
+        let (headers, _) = collect_headers(&values);
+        let schema: Vec<String> = ["udiDiCode", "legacyCode", "basicUdi"]
+            .map(String::from)
+            .to_vec();
+
+        let strict = apply_schema(headers.clone(), &schema, true);
+        assert_eq!(strict, schema);
+        let lenient = apply_schema(headers.clone(), &schema, false);
+        assert_eq!(lenient[..3], schema[..]);
+        let extra: Vec<&String> = headers.iter().filter(|h| !schema.contains(h)).collect();
+        assert_eq!(lenient[3..].iter().collect::<Vec<_>>(), extra);
+
+        let rows = RowBuilder::from_values(&values, ValueFormat::DEFAULT).into_rows(&strict);
+        assert!(rows.iter().all(|r| r[1].is_empty() && !r[2].is_empty()));
+
+        let path = std::env::temp_dir().join(format!(
+            "swissdamed2sqlite_schema_test_{}.txt",
+            std::process::id()
+        ));
+        write_schema(&schema, &path).unwrap();
+        assert_eq!(read_schema(&path).unwrap(), schema);
+        std::fs::write(&path, "# columns\nudiDiCode\n\nbasicUdi\nudiDiCode\n").unwrap();
+        let err = read_schema(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+        assert!(err.contains("Duplicate column 'udiDiCode'"), "{}", err);
+    }
+
+    /// `--status` matches `status` or else `deviceStatus`, trimmed and
+    /// case-insensitively; items without either are dropped.
+    #[test]
+    fn status_filter_matches_either_key() {
+        let statuses = ["on_the_market".to_string(), " Suspended ".to_string()];
+        let mut filter = ItemFilter::new(&FilterOptions {
+            statuses: &statuses,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(filter.is_active());
+        let cases = [
+            (serde_json::json!({"status": "ON_THE_MARKET"}), true),
+            (serde_json::json!({"deviceStatus": "suspended"}), true),
+            (
+                serde_json::json!({"status": null, "deviceStatus": "On_The_Market"}),
+                true,
+            ),
+            (serde_json::json!({"status": "NO_LONGER_PLACED"}), false),
+            (serde_json::json!({"status": "on_the_market_soon"}), false),
+            (serde_json::json!({"basicUdi": "B1"}), false),
+        ];
+        for (item, expected) in &cases {
+            assert_eq!(filter.keep(item), *expected, "{}", item);
+        }
+        assert_eq!((filter.kept, filter.total), (3, 6));
+        assert!(!ItemFilter::new(&FilterOptions::default())
+            .unwrap()
+            .is_active());
+    }
+
+    /// `--company` patterns match case-insensitively, as a substring or as
+    /// a `/regex/`; an item passes if any pattern matches, and the hits are
+    /// counted per pattern.
+    #[test]
+    fn company_patterns_match_case_insensitively() {
+        let cases = [
+            ("sigvaris", "SIGVARIS AG", true),
+            ("Muster Med", "muster medical ag", true),
+            ("Muster Med", "Muster AG", false),
+            ("/^ivf\\b/", "IVF Hartmann AG", true),
+            ("/^ivf\\b/", "IVFX AG", false),
+            ("/hart+mann|paul/", "Paul Hartmann AG", true),
+            ("//", "a // b", true),
+            ("/", "and/or", true),
+        ];
+        for (pattern, company, expected) in cases {
+            let parsed = CompanyPattern::parse(pattern).unwrap();
+            assert_eq!(
+                parsed.is_match(company),
+                expected,
+                "{} {}",
+                pattern,
+                company
+            );
+        }
+        assert!(CompanyPattern::parse("/(unclosed/").is_err());
+
+        let companies = [
+            "sigvaris".to_string(),
+            "/hartmann$/".to_string(),
+            "typo".to_string(),
+        ];
+        let mut filter = ItemFilter::new(&FilterOptions {
+            companies: &companies,
+            ..Default::default()
+        })
+        .unwrap();
+        let kept: Vec<bool> = ["Sigvaris AG", "Paul HARTMANN", "Other GmbH", ""]
+            .iter()
+            .map(|c| filter.keep(&serde_json::json!({ "companyName": c })))
+            .collect();
+        assert_eq!(kept, [true, true, false, false]);
+        assert_eq!(filter.company_hits, [1, 1, 0]);
+    }
+
+    /// Risk class spellings normalize to one form, and `--risk-class`
+    /// drops items without a class unless `--include-unknown-risk-class`.
+    #[test]
+    fn risk_classes_normalize_and_filter() {
+        let cases = [
+            ("I", "I"),
+            ("i", "I"),
+            ("Class I", "I"),
+            ("CLASS_I", "I"),
+            (" class_iia ", "IIA"),
+            ("Class IIa", "IIA"),
+            ("IIb", "IIB"),
+            ("CLASS-III", "III"),
+            ("classIII", "III"),
+            ("Class A", "A"),
+            ("", ""),
+            ("  ", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_risk_class(input), expected, "{:?}", input);
+        }
+
+        let risk_classes = ["Class I".to_string(), "iia".to_string()];
+        let items = ["CLASS_I", "CLASS_IIA", "CLASS_IIB", ""]
+            .map(|c| serde_json::json!({ "riskClass": c }));
+        for (include_unknown, expected) in [
+            (false, [true, true, false, false]),
+            (true, [true, true, false, true]),
+        ] {
+            let mut filter = ItemFilter::new(&FilterOptions {
+                risk_classes: &risk_classes,
+                include_unknown_risk_class: include_unknown,
+                ..Default::default()
+            })
+            .unwrap();
+            let kept = items.clone().map(|item| filter.keep(&item));
+            assert_eq!(kept, expected, "include_unknown: {}", include_unknown);
+            let seen: Vec<(&str, usize)> = filter
+                .risk_class_counts
+                .iter()
+                .map(|(c, n)| (c.as_str(), *n))
+                .collect();
+            assert_eq!(seen, [("", 1), ("I", 1), ("IIA", 1), ("IIB", 1)]);
+        }
+        let mut unfiltered = ItemFilter::new(&FilterOptions::default()).unwrap();
+        assert!(unfiltered.keep(&items[3]));
+    }
+
+    /// Duplicate keys are counted per value in order of first appearance,
+    /// empty keys ignored; `--fail-on-dup-key` turns them into an error
+    /// listing at most five examples.
+    #[test]
+    fn duplicate_row_keys_counts_and_fails() {
+        let headers: Vec<String> = ["udiDiCode", "tradeName_language"]
+            .map(String::from)
+            .to_vec();
+        let codes = [
+            "C3", "C1", "C3", "", "C2", "C1", "C3", "", "D1", "D1", "D2", "D2", "D3", "D3", "D4",
+            "D4",
+        ];
+        let rows: Vec<Vec<String>> = codes
+            .iter()
+            .map(|c| vec![c.to_string(), "DE".to_string()])
+            .collect();
+        let dups = duplicate_row_keys(&headers, &rows, &["udiDiCode"]);
+        let expected = [
+            ("C3", 3),
+            ("C1", 2),
+            ("D1", 2),
+            ("D2", 2),
+            ("D3", 2),
+            ("D4", 2),
+        ];
+        assert_eq!(dups, expected.map(|(c, n)| (c.to_string(), n)).to_vec());
+        assert!(duplicate_row_keys(&headers, &rows, &["basicUdi"]).is_empty());
+        let long_key = ["udiDiCode", "tradeName_language"];
+        assert_eq!(
+            duplicate_row_keys(&headers, &rows, &long_key)[0],
+            ("C3/DE".to_string(), 3)
+        );
+
+        check_duplicate_row_keys(&headers, &rows, &["udiDiCode"], false).unwrap();
+        check_duplicate_row_keys(&headers, &rows[..2], &["udiDiCode"], true).unwrap();
+        let err = check_duplicate_row_keys(&headers, &rows, &["udiDiCode"], true).unwrap_err();
+        assert_eq!(
+            err,
+            "6 udiDiCode value(s) occur in more than one row (13 rows), \
+             e.g. C3 (3 rows), C1 (2 rows), D1 (2 rows), D2 (2 rows), D3 (2 rows)"
+        );
+    }
+
+    /// `--dedup` keeps the last item per key at the key's first position,
+    /// keeps items without the key, and columns only a dropped item had
+    /// don't reach `collect_headers`.
+    #[test]
+    fn dedup_by_key_keeps_last_item() {
+        let values = vec![
+            serde_json::json!({"basicUdi": "B1", "deviceName": "old", "legacyCode": "L1"}),
+            serde_json::json!({"deviceName": "no key"}),
+            serde_json::json!({"basicUdi": "B2", "deviceName": "only"}),
+            serde_json::json!({"basicUdi": null, "deviceName": "null key"}),
+            serde_json::json!({"basicUdi": "B1", "deviceName": "new"}),
+            serde_json::json!({"deviceName": "no key"}),
+            serde_json::json!({"basicUdi": "B1", "deviceName": "newest"}),
+        ];
+        let (kept, dropped) = dedup_by_key(values, "basicUdi");
+        let names: Vec<String> = kept.iter().map(|v| get_field(v, "deviceName")).collect();
+        assert_eq!(names, ["newest", "no key", "only", "null key", "no key"]);
+        assert_eq!(dropped, ["B1", "B1"]);
+        let (headers, _) = collect_headers(&kept);
+        assert!(!headers.iter().any(|h| h == "legacyCode"), "{:?}", headers);
+    }
+
+    /// Without `--strict`, non-object entries and items whose udiDis or
+    /// tradeNames is not an array are counted; with it the first one is an
+    /// error naming its index, with a snippet cut at 120 characters.
+    #[test]
+    fn shape_check_counts_or_fails() {
+        let items = [
+            serde_json::json!({"basicUdi": "B1", "udiDis": [{"tradeNames": []}]}),
+            serde_json::json!("not an item"),
+            serde_json::json!({"basicUdi": "B2", "udiDis": {"udiDiCode": "0764002"}}),
+            serde_json::json!({"basicUdi": "B3", "udiDis": [{"tradeNames": "Pflaster"}]}),
+            serde_json::json!(42),
+        ];
+        let mut lenient = ShapeCheck::new(false);
+        for item in &items {
+            lenient.check(item).unwrap();
+        }
+        assert_eq!((lenient.skipped, lenient.malformed), (2, 2));
+
+        let mut strict = ShapeCheck::new(true);
+        strict.check(&items[0]).unwrap();
+        assert_eq!(
+            strict.check(&items[1]).unwrap_err(),
+            "Item 1: entry is not an object (--strict): \"not an item\""
+        );
+        for (item, problem) in [
+            (&items[2], "udiDis is not an array"),
+            (&items[3], "udiDis[0].tradeNames is not an array"),
+        ] {
+            let err = ShapeCheck::new(true).check(item).unwrap_err();
+            let expected = format!("Item 0: {} (--strict): {{\"basicUdi\"", problem);
+            assert!(err.starts_with(&expected), "{}", err);
+        }
+        let long = serde_json::json!("x".repeat(500));
+        let err = ShapeCheck::new(true).check(&long).unwrap_err();
+        assert!(err.ends_with(&format!("\"{}…", "x".repeat(119))), "{}", err);
+    }
+
+    /// `--sort-by basicUdi,udiDiCode` writes the same rows to a
+    /// byte-identical CSV whatever order they arrive in, empty keys first.
+    #[test]
+    fn sorted_rows_write_identical_csv() {
+        let headers: Vec<String> = ["basicUdi", "udiDiCode", "deviceName"]
+            .map(String::from)
+            .to_vec();
+        let rows: Vec<Vec<String>> = [
+            ["B2", "0764002", "Binde"],
+            ["B1", "0764003", "Pflaster"],
+            ["", "0764009", "Schiene"],
+            ["B1", "0764001", "Verband"],
+            ["B2", "", "Kompresse"],
+            ["", "", "Watte"],
+        ]
+        .iter()
+        .map(|r| r.map(String::from).to_vec())
+        .collect();
+        let mut shuffled = rows.clone();
+        shuffled.rotate_left(2);
+        shuffled.reverse();
+        assert_ne!(shuffled, rows);
+
+        let columns = ["basicUdi", "udiDiCode"].map(String::from).to_vec();
+        let dir = std::env::temp_dir();
+        let mut written = Vec::new();
+        for (n, mut input) in [rows, shuffled].into_iter().enumerate() {
+            sort_rows(&headers, &mut input, &columns).unwrap();
+            assert_eq!(input[0], ["", "", "Watte"]);
+            assert_eq!(input[1], ["", "0764009", "Schiene"]);
+            assert_eq!(input[2][..2], ["B1", "0764001"]);
+            let path = dir.join(format!(
+                "swissdamed2sqlite_sort_test_{}_{}.csv",
+                std::process::id(),
+                n
+            ));
+            let filename = crate::export::write_csv_with(
+                &headers,
+                &input,
+                &path.to_string_lossy(),
+                &crate::export::CsvOptions::default(),
+            )
+            .unwrap();
+            written.push(std::fs::read(&filename).unwrap());
+            std::fs::remove_file(&filename).ok();
+        }
+        assert_eq!(written[0], written[1]);
+        let unknown = ["size"].map(String::from).to_vec();
+        assert!(sort_rows(&headers, &mut [], &unknown).is_err());
+    }
+
+    /// `--schema-snapshot` reports added and removed columns in list order;
+    /// `--fail-on-schema-change` turns a change into an error, while a
+    /// missing snapshot or an unchanged list passes.
+    #[test]
+    fn schema_snapshot_reports_changes() {
+        let cols =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|s| s.to_string()).collect() };
+        let previous = cols(&["basicUdi", "udiDiCode", "legacyCode"]);
+        let current = cols(&["basicUdi", "riskClass", "udiDiCode", "tradeName_DE"]);
+        assert_eq!(
+            schema_changes(&previous, &current),
+            (cols(&["riskClass", "tradeName_DE"]), cols(&["legacyCode"]))
+        );
+        assert_eq!(schema_changes(&current, &current), (vec![], vec![]));
+
+        let path = std::env::temp_dir().join(format!(
+            "swissdamed2sqlite_snapshot_test_{}.txt",
+            std::process::id()
+        ));
+        std::fs::remove_file(&path).ok();
+        check_schema_snapshot(&path, &current, true).unwrap();
+        write_schema(&previous, &path).unwrap();
+        check_schema_snapshot(&path, &previous, true).unwrap();
+        check_schema_snapshot(&path, &current, false).unwrap();
+        let err = check_schema_snapshot(&path, &current, true)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(read_schema(&path).unwrap(), previous);
+        std::fs::remove_file(&path).ok();
+        assert!(
+            err.contains("2 column(s) added [riskClass, tradeName_DE], 1 removed [legacyCode]"),
+            "{}",
+            err
+        );
+    }
+
+    /// `--flatten-depth` expands nested objects level by level in source
+    /// order; deeper and empty objects stay single JSON fields.
+    #[test]
+    fn flattened_fields_follow_depth() {
+        let item = serde_json::json!({
+            "basicUdi": "B1",
+            "manufacturer": {"name": "Acme", "address": {"city": "Bern"}},
+            "extra": {},
+        });
+        let map = item.as_object().unwrap();
+        let names = |depth: usize| {
+            flattened_fields(map, depth)
+                .into_iter()
+                .map(|(name, val)| format!("{}={}", name, val))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(0),
+            [
+                "basicUdi=\"B1\"",
+                "manufacturer={\"name\":\"Acme\",\"address\":{\"city\":\"Bern\"}}",
+                "extra={}",
+            ]
+        );
+        assert_eq!(
+            names(1),
+            [
+                "basicUdi=\"B1\"",
+                "manufacturer.name=\"Acme\"",
+                "manufacturer.address={\"city\":\"Bern\"}",
+                "extra={}",
+            ]
+        );
+        assert_eq!(
+            names(2),
+            [
+                "basicUdi=\"B1\"",
+                "manufacturer.name=\"Acme\"",
+                "manufacturer.address.city=\"Bern\"",
+                "extra={}",
+            ]
+        );
+    }
+
+    /// Only non-empty arrays whose every element has a string `language`
+    /// or `lang` count as language-tagged.
+    #[test]
+    fn is_lang_tagged_needs_every_element_tagged() {
+        let tagged = |v: Value| is_lang_tagged(v.as_array().unwrap());
+        assert!(tagged(serde_json::json!([
+            {"language": "DE", "textValue": "Pflaster"},
+            {"lang": "FR", "value": "Pansement"},
+        ])));
+        assert!(!tagged(serde_json::json!([])));
+        assert!(!tagged(serde_json::json!([
+            {"language": "DE", "textValue": "Pflaster"},
+            {"textValue": "untagged"},
+        ])));
+        assert!(!tagged(serde_json::json!([{"language": 7}])));
+        assert!(!tagged(serde_json::json!(["DE", "FR"])));
+    }
+
+    /// With `--split-lang-arrays` a tagged array becomes sorted
+    /// `<field>_<LANG>` cells; untagged arrays and `udiDis` are left alone.
+    #[test]
+    fn main_cells_split_lang_arrays() {
+        let item = serde_json::json!({
+            "basicUdi": "B1",
+            "descriptions": [
+                {"language": "IT", "textValue": "Cerotto"},
+                {"language": "DE", "textValue": "Pflaster"},
+                {"language": "DE", "textValue": "steril"},
+            ],
+            "tags": ["a", "b"],
+            "udiDis": [{"udiDiCode": "0764"}],
+        });
+        let map = item.as_object().unwrap();
+        let cells = |split_lang_arrays: bool| {
+            let fmt = ValueFormat {
+                split_lang_arrays,
+                ..ValueFormat::DEFAULT
+            };
+            main_cells(map, &fmt)
+                .into_iter()
+                .map(|(name, cell)| format!("{}={}", name, cell))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            cells(true),
+            [
+                "basicUdi=B1",
+                "descriptions_DE=Pflaster | steril",
+                "descriptions_IT=Cerotto",
+                "tags=a | b",
+            ]
+        );
+        assert_eq!(
+            cells(false),
+            [
+                "basicUdi=B1",
+                "descriptions=IT: Cerotto | DE: Pflaster | DE: steril",
+                "tags=a | b",
+            ]
+        );
+    }
+
+    /// `--collapse-whitespace` flattens plain strings, language-tagged
+    /// arrays and trade names alike.
+    #[test]
+    fn collapse_whitespace_in_cells() {
+        let fmt = ValueFormat {
+            collapse_whitespace: true,
+            ..ValueFormat::DEFAULT
+        };
+        let text = serde_json::json!("  Knee\tbrace \n\n  left ");
+        assert_eq!(value_to_string_with(&text, &fmt), "Knee brace left");
+        assert_eq!(
+            value_to_string(&text),
+            "Knee\tbrace \n\n  left",
+            "off by default"
+        );
+        let tagged = serde_json::json!([{"language": "DE", "textValue": "Knie-\r\n  bandage"}]);
+        assert_eq!(value_to_string_with(&tagged, &fmt), "DE: Knie- bandage");
+        let names = texts_by_lang(tagged.as_array().unwrap(), &fmt);
+        assert_eq!(names["DE"], "Knie- bandage");
+    }
+}
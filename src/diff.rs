@@ -1,32 +1,187 @@
-use csv::WriterBuilder;
+use log::info;
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 
-use crate::app_data_dir;
+use crate::export::{
+    detect_header_lang, localize_header, localize_headers, technical_headers, CsvFileWriter,
+    CsvOptions, HeaderLang,
+};
 
-fn extract_date_from_filename(path: &PathBuf) -> Option<String> {
-    let stem = path.file_stem()?.to_str()?;
-    // Expected format: swissdamed_dd.mm.yyyy
-    let date = stem.rsplit('_').next()?;
-    if date.len() == 10 && date.chars().filter(|c| *c == '.').count() == 2 {
-        Some(date.to_string())
-    } else {
-        None
+/// A parsed CSV file: header row plus data rows.
+pub type CsvTable = (Vec<String>, Vec<Vec<String>>);
+
+/// Extra column of the row-level diff when the headers drifted: the added
+/// or removed column of a `column_added`/`column_removed` row, empty on
+/// data rows.
+pub const DRIFT_COLUMN: &str = "diff_column";
+
+/// Options for [`diff_csv_files`].
+#[derive(Default)]
+pub struct DiffOptions {
+    /// Only report counts: print added/removed/changed totals plus per-column
+    /// change counts and write `diff/summary_<old>_<new>.csv` instead of the
+    /// row-level diff CSV.
+    pub summary: bool,
+    /// Join-key column(s) used to pair old and new rows. Several columns form
+    /// a composite key; empty means the default `udiDiCode`.
+    pub keys: Vec<String>,
+    /// Diff files whose headers differ (e.g. a new `tradeName_XX` column) by
+    /// aligning both on their shared columns instead of aborting.
+    pub allow_header_drift: bool,
+    /// Output settings for the diff/summary CSV (e.g. `--no-bom`).
+    pub csv: CsvOptions,
+    /// Also write the row-level diff as `diff/diff_swissdamed_<old>_<new>.html`
+    /// (see [`write_diff_html`]).
+    pub html: bool,
+}
+
+/// Re-order `rows` (laid out as `from`) into the `to` column layout.
+fn project_rows(rows: &[Vec<String>], from: &[String], to: &[String]) -> Vec<Vec<String>> {
+    let idxs: Vec<Option<usize>> = to
+        .iter()
+        .map(|h| from.iter().position(|f| f == h))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            idxs.iter()
+                .map(|i| i.and_then(|i| row.get(i)).cloned().unwrap_or_default())
+                .collect()
+        })
+        .collect()
+}
+
+/// Resolve the key columns to their indices in `headers`.
+fn key_indices(
+    headers: &[String],
+    keys: &[String],
+    path: &Path,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    keys.iter()
+        .map(|key| {
+            headers.iter().position(|h| h == key).ok_or_else(|| {
+                format!("Key column '{}' not found in {}", key, path.display()).into()
+            })
+        })
+        .collect()
+}
+
+/// Build the (possibly composite) join key for a row.
+fn row_key(row: &[String], key_idxs: &[usize]) -> String {
+    key_idxs
+        .iter()
+        .map(|&i| row.get(i).map(String::as_str).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\u{1f}")
+}
+
+/// The date of an export named like `swissdamed_DD.MM.YYYY.csv` or
+/// `swissdamed_YYYY-MM-DD.csv` (optionally `.gz`/`.zst` compressed). When
+/// the file name has no date, the folders a `--name-template` such as
+/// `{date}/{name}.{ext}` adds are searched, innermost first.
+pub fn filename_date(path: &Path) -> Option<chrono::NaiveDate> {
+    path_date(path, crate::export::name_template_depth())
+}
+
+/// [`filename_date`] searching `folder_depth` parent folders.
+fn path_date(path: &Path, folder_depth: usize) -> Option<chrono::NaiveDate> {
+    let name = path.file_name()?.to_str()?;
+    let name = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name);
+    let stem = Path::new(name).file_stem()?.to_str()?;
+    let folders = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .take(folder_depth)
+        .filter_map(|dir| dir.file_name()?.to_str());
+    std::iter::once(stem)
+        .chain(folders)
+        .find_map(rightmost_date_stamp)
+}
+
+/// Rightmost date stamp in `s`, wherever a `--name-template` put it.
+fn rightmost_date_stamp(s: &str) -> Option<chrono::NaiveDate> {
+    (0..s.len().saturating_sub(9))
+        .rev()
+        .filter(|&i| s.is_char_boundary(i) && s.is_char_boundary(i + 10))
+        .find_map(|i| crate::export::parse_date_stamp(&s[i..i + 10]))
+}
+
+/// The date stamp of an export filename in either style, re-rendered in
+/// the current `--date-format` so derived filenames stay consistent.
+pub fn extract_date_from_filename(path: &Path) -> Option<String> {
+    filename_date(path).map(|d| crate::export::date_format().stamp(d))
+}
+
+/// Guess the delimiter of CSV `data` from its header line: whichever of
+/// `,`, `;` and tab occurs most often outside quotes, preferring
+/// `fallback` on a tie. Returns `fallback` if none occurs.
+pub fn detect_delimiter(data: &[u8], fallback: u8) -> u8 {
+    let mut counts = [(b',', 0usize), (b';', 0), (b'\t', 0)];
+    let mut in_quotes = false;
+    for &b in data {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b'\n' | b'\r' if !in_quotes => break,
+            _ if !in_quotes => {
+                if let Some(c) = counts.iter_mut().find(|(d, _)| *d == b) {
+                    c.1 += 1;
+                }
+            }
+            _ => {}
+        }
     }
+    let best = counts.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    if best == 0 || counts.iter().any(|(d, n)| *d == fallback && *n == best) {
+        return fallback;
+    }
+    counts
+        .iter()
+        .find(|(_, n)| *n == best)
+        .map_or(fallback, |(d, _)| *d)
 }
 
-fn read_csv_rows(
-    path: &PathBuf,
-) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn std::error::Error>> {
+/// Read a file, decompressing gzip or zstd (detected by their magic bytes,
+/// as written by `--compress`).
+fn read_maybe_compressed(path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    use std::io::Read;
+
     let data = fs::read(path)?;
+    let mut out = Vec::new();
+    if data.starts_with(&[0x1f, 0x8b]) {
+        flate2::read::MultiGzDecoder::new(&data[..])
+            .read_to_end(&mut out)
+            .map_err(|e| format!("{}: invalid gzip data: {}", path.display(), e))?;
+    } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        zstd::stream::copy_decode(&data[..], &mut out)
+            .map_err(|e| format!("{}: invalid zstd data: {}", path.display(), e))?;
+    } else {
+        return Ok(data);
+    }
+    Ok(out)
+}
+
+/// Read a CSV export (BOM optional, plain or `.gz`/`.zst`) into its header row and data rows. The
+/// delimiter is detected from the header line (see [`detect_delimiter`]),
+/// so files written with another `--delimiter` still read correctly;
+/// `opts.delimiter` breaks ties.
+pub fn read_csv_rows(
+    path: &Path,
+    opts: &CsvOptions,
+) -> Result<CsvTable, Box<dyn std::error::Error>> {
+    let data = read_maybe_compressed(path)?;
     // Skip UTF-8 BOM if present
     let data = if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
         &data[3..]
     } else {
         &data
     };
-    let mut rdr = csv::ReaderBuilder::new().from_reader(data);
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(detect_delimiter(data, opts.delimiter))
+        .from_reader(data);
     let headers: Vec<String> = rdr.headers()?.iter().map(|s| s.to_string()).collect();
     let mut rows = Vec::new();
     for result in rdr.records() {
@@ -37,34 +192,98 @@ fn read_csv_rows(
 }
 
 pub fn diff_csv_files(
-    old_path: &PathBuf,
-    new_path: &PathBuf,
+    old_path: &Path,
+    new_path: &Path,
+    opts: &DiffOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let (old_headers, old_rows) = read_csv_rows(old_path)?;
-    let (new_headers, new_rows) = read_csv_rows(new_path)?;
+    let (old_headers, old_rows) = read_csv_rows(old_path, &opts.csv)?;
+    let (new_headers, new_rows) = read_csv_rows(new_path, &opts.csv)?;
 
-    if old_headers != new_headers {
-        return Err("CSV files have different headers — cannot diff".into());
+    // Both files must use the same --header-lang; compare on the technical
+    // names and write the diff with the files' header language.
+    let header_lang = detect_header_lang(&old_headers);
+    if detect_header_lang(&new_headers) != header_lang {
+        let describe = |lang: HeaderLang| match lang {
+            HeaderLang::Technical => "technical",
+            HeaderLang::De => "German (--header-lang de)",
+        };
+        return Err(format!(
+            "CSV files use different header languages — cannot diff: {} has {} headers, {} has {} \
+             headers; export both with the same --header-lang",
+            old_path.display(),
+            describe(header_lang),
+            new_path.display(),
+            describe(detect_header_lang(&new_headers))
+        )
+        .into());
     }
+    let old_headers = technical_headers(&old_headers, header_lang);
+    let new_headers = technical_headers(&new_headers, header_lang);
+    let csv_opts = CsvOptions {
+        header_lang,
+        ..opts.csv
+    };
+
+    let default_keys = ["udiDiCode".to_string()];
+    let given_keys = technical_headers(&opts.keys, header_lang);
+    let keys: &[String] = if given_keys.is_empty() {
+        &default_keys
+    } else {
+        &given_keys
+    };
+    key_indices(&old_headers, keys, old_path)?;
+    key_indices(&new_headers, keys, new_path)?;
 
-    let key_col = "udiDiCode";
-    let key_idx = old_headers
+    let added_columns: Vec<String> = new_headers
+        .iter()
+        .filter(|h| !old_headers.contains(h))
+        .cloned()
+        .collect();
+    let removed_columns: Vec<String> = old_headers
         .iter()
-        .position(|h| h == key_col)
-        .ok_or_else(|| format!("Column '{}' not found in headers", key_col))?;
+        .filter(|h| !new_headers.contains(h))
+        .cloned()
+        .collect();
 
-    // Build maps: udiDiCode -> Vec<row>
+    let (headers, old_rows, new_rows) = if old_headers == new_headers {
+        (old_headers, old_rows, new_rows)
+    } else if opts.allow_header_drift {
+        // Align both files on their shared columns (in old-file order) so a
+        // new sparse column doesn't mark every row as changed.
+        let shared: Vec<String> = old_headers
+            .iter()
+            .filter(|h| new_headers.contains(h))
+            .cloned()
+            .collect();
+        let old_rows = project_rows(&old_rows, &old_headers, &shared);
+        let new_rows = project_rows(&new_rows, &new_headers, &shared);
+        if !added_columns.is_empty() {
+            info!("Columns added: {}", added_columns.join(", "));
+        }
+        if !removed_columns.is_empty() {
+            info!("Columns removed: {}", removed_columns.join(", "));
+        }
+        info!("Comparing {} shared columns.", shared.len());
+        (shared, old_rows, new_rows)
+    } else {
+        return Err("CSV files have different headers — cannot diff \
+                    (use --diff-allow-header-drift to compare the shared columns)"
+            .into());
+    };
+    let key_idxs = key_indices(&headers, keys, old_path)?;
+
+    // Build maps: key -> Vec<row>
     let mut old_map: HashMap<String, Vec<Vec<String>>> = HashMap::new();
     for row in &old_rows {
         old_map
-            .entry(row[key_idx].clone())
+            .entry(row_key(row, &key_idxs))
             .or_default()
             .push(row.clone());
     }
     let mut new_map: HashMap<String, Vec<Vec<String>>> = HashMap::new();
     for row in &new_rows {
         new_map
-            .entry(row[key_idx].clone())
+            .entry(row_key(row, &key_idxs))
             .or_default()
             .push(row.clone());
     }
@@ -92,64 +311,426 @@ pub fn diff_csv_files(
         }
     }
 
-    // Changed: keys in both but rows differ
+    // Changed: keys in both but rows differ. Old/new rows that no longer
+    // match are paired in order so their cells can be compared column by column.
+    let mut column_changes: Vec<usize> = vec![0; headers.len()];
+    let mut changed_keys = 0;
     for key in old_keys.intersection(&new_keys) {
         let old_set: HashSet<&Vec<String>> = old_map[key].iter().collect();
         let new_set: HashSet<&Vec<String>> = new_map[key].iter().collect();
         if old_set != new_set {
-            for row in &old_map[key] {
-                if !new_set.contains(row) {
-                    diff_rows.push(("changed_old".to_string(), row.clone()));
+            changed_keys += 1;
+            let changed_old: Vec<&Vec<String>> = old_map[key]
+                .iter()
+                .filter(|row| !new_set.contains(row))
+                .collect();
+            let changed_new: Vec<&Vec<String>> = new_map[key]
+                .iter()
+                .filter(|row| !old_set.contains(row))
+                .collect();
+            for (old_row, new_row) in changed_old.iter().zip(&changed_new) {
+                for (i, count) in column_changes.iter_mut().enumerate() {
+                    if old_row.get(i) != new_row.get(i) {
+                        *count += 1;
+                    }
                 }
             }
-            for row in &new_map[key] {
-                if !old_set.contains(row) {
-                    diff_rows.push(("changed_new".to_string(), row.clone()));
-                }
+            for row in changed_old {
+                diff_rows.push(("changed_old".to_string(), row.clone()));
+            }
+            for row in changed_new {
+                diff_rows.push(("changed_new".to_string(), row.clone()));
             }
         }
     }
 
-    if diff_rows.is_empty() {
-        eprintln!("No differences found.");
+    if diff_rows.is_empty() && added_columns.is_empty() && removed_columns.is_empty() {
+        info!("No differences found.");
         return Ok(());
     }
 
     // Build output filename from dates in input filenames
     let old_date = extract_date_from_filename(old_path).unwrap_or_else(|| "unknown".to_string());
     let new_date = extract_date_from_filename(new_path).unwrap_or_else(|| "unknown".to_string());
-    let diff_dir = app_data_dir().join("diff");
-    fs::create_dir_all(&diff_dir)?;
-    let out_filename = diff_dir
-        .join(format!("diff_swissdamed_{}_{}.csv", old_date, new_date))
-        .to_string_lossy()
-        .to_string();
+    let dates = format!("{}_{}", old_date, new_date);
 
-    let mut out_headers = vec!["diff_status".to_string()];
-    out_headers.extend(old_headers);
+    if opts.summary {
+        let added_keys = new_keys.difference(&old_keys).count();
+        let removed_keys = old_keys.difference(&new_keys).count();
 
-    let mut wtr = WriterBuilder::new().from_writer(Vec::new());
-    wtr.write_record(&out_headers)?;
-    for (status, row) in &diff_rows {
-        let mut full_row = vec![status.clone()];
-        full_row.extend(row.clone());
-        wtr.write_record(&full_row)?;
+        let mut by_column: Vec<(&String, usize)> = headers
+            .iter()
+            .zip(column_changes.iter().copied())
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        by_column.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        // The table goes to stderr unconditionally, also under -q
+        eprintln!(
+            "Keys: {} added, {} removed, {} changed",
+            added_keys, removed_keys, changed_keys
+        );
+        eprintln!("  {:<40} {:>10}", "Column", "Changes");
+        eprintln!("  {}", "-".repeat(51));
+        for (column, count) in &by_column {
+            eprintln!("  {:<40} {:>10}", column, count);
+        }
+
+        let out_filename = crate::export::output_diff("summary", &dates)?;
+        let mut wtr = CsvFileWriter::create(&out_filename, &csv_opts)?;
+        wtr.write_record(["metric", "count"])?;
+        wtr.write_record(["keys_added", &added_keys.to_string()])?;
+        wtr.write_record(["keys_removed", &removed_keys.to_string()])?;
+        wtr.write_record(["keys_changed", &changed_keys.to_string()])?;
+        for column in &added_columns {
+            wtr.write_record([format!("column_added:{}", column), String::new()])?;
+        }
+        for column in &removed_columns {
+            wtr.write_record([format!("column_removed:{}", column), String::new()])?;
+        }
+        for (column, count) in &by_column {
+            wtr.write_record([format!("column:{}", column), count.to_string()])?;
+        }
+        let out_filename = wtr.finish()?;
+        info!("Diff summary written: {}", out_filename);
+        crate::manifest::record_file(&out_filename);
+        return Ok(());
     }
-    let data = wtr.into_inner()?;
+    let out_filename = crate::export::output_diff("diff_swissdamed", &dates)?;
 
-    let mut output = Vec::with_capacity(3 + data.len());
-    output.extend_from_slice(b"\xEF\xBB\xBF");
-    output.extend_from_slice(&data);
+    // Schema drift rows name their column in DRIFT_COLUMN, which only
+    // exists when the headers drifted.
+    let drift = !added_columns.is_empty() || !removed_columns.is_empty();
+    let mut out_headers = vec!["diff_status".to_string()];
+    if drift {
+        out_headers.push(DRIFT_COLUMN.to_string());
+    }
+    out_headers.extend(headers);
 
-    fs::write(&out_filename, output)?;
+    let mut wtr = CsvFileWriter::create(&out_filename, &csv_opts)?;
+    wtr.write_record(localize_headers(&out_headers, header_lang))?;
+    for (status, columns) in [
+        ("column_added", &added_columns),
+        ("column_removed", &removed_columns),
+    ] {
+        for column in columns {
+            let mut record = vec![String::new(); out_headers.len()];
+            record[0] = status.to_string();
+            record[1] = localize_header(column, header_lang);
+            wtr.write_record(&record)?;
+        }
+    }
+    let drift_cell = drift.then(String::new);
+    for (status, row) in &diff_rows {
+        wtr.write_record(std::iter::once(status).chain(&drift_cell).chain(row))?;
+    }
+    let out_filename = wtr.finish()?;
 
     let added = diff_rows.iter().filter(|(s, _)| s == "added").count();
     let removed = diff_rows.iter().filter(|(s, _)| s == "removed").count();
     let changed = diff_rows.iter().filter(|(s, _)| s == "changed_new").count();
-    eprintln!(
+    info!(
         "Diff written: {} ({} added, {} removed, {} changed)",
         out_filename, added, removed, changed,
     );
+    crate::manifest::record_table(
+        None,
+        &out_headers,
+        added_columns.len() + removed_columns.len() + diff_rows.len(),
+    );
+    crate::manifest::record_file(&out_filename);
+
+    if opts.html {
+        let html_filename = crate::export::output_diff_html("diff_swissdamed", &dates)?;
+        let title = format!(
+            "swissdamed diff {} → {}",
+            old_path.display(),
+            new_path.display()
+        );
+        // The HTML lists the drifted columns above the table instead
+        let table_headers: Vec<String> = out_headers
+            .iter()
+            .filter(|h| *h != DRIFT_COLUMN)
+            .cloned()
+            .collect();
+        let drift_columns = [
+            ("column_added", added_columns.as_slice()),
+            ("column_removed", removed_columns.as_slice()),
+        ];
+        write_diff_html(
+            &html_filename,
+            &title,
+            &table_headers,
+            &drift_columns,
+            &diff_rows,
+            &key_idxs,
+            header_lang,
+        )?;
+        info!("Diff HTML written: {}", html_filename);
+        crate::manifest::record_file(&html_filename);
+    }
+
+    Ok(())
+}
+
+/// Escape `&`, `<`, `>` and quotes for HTML text and attributes.
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+/// Write the row-level diff as a standalone HTML table for reviewers:
+/// added rows green, removed red, changed yellow, and in each
+/// changed_old/changed_new pair (paired per key in order, as for the
+/// per-column counts) the cells that differ highlighted. `headers` starts
+/// with `diff_status`; the `drift` columns are listed above the table.
+fn write_diff_html(
+    filename: &str,
+    title: &str,
+    headers: &[String],
+    drift: &[(&str, &[String])],
+    diff_rows: &[(String, Vec<String>)],
+    key_idxs: &[usize],
+    header_lang: HeaderLang,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Partner row of each changed_old/changed_new row
+    let mut by_key: HashMap<String, (Vec<usize>, Vec<usize>)> = HashMap::new();
+    for (i, (status, row)) in diff_rows.iter().enumerate() {
+        let entry = by_key.entry(row_key(row, key_idxs)).or_default();
+        match status.as_str() {
+            "changed_old" => entry.0.push(i),
+            "changed_new" => entry.1.push(i),
+            _ => {}
+        }
+    }
+    let mut partner: HashMap<usize, usize> = HashMap::new();
+    for (old, new) in by_key.values() {
+        for (&o, &n) in old.iter().zip(new) {
+            partner.insert(o, n);
+            partner.insert(n, o);
+        }
+    }
+
+    let count = |status: &str| diff_rows.iter().filter(|(s, _)| s == status).count();
+    let mut html = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"UTF-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(title)));
+    html.push_str(
+        "<style>\n\
+         body { font-family: Arial, sans-serif; margin: 20px; }\n\
+         table { border-collapse: collapse; }\n\
+         th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }\n\
+         th { background: #2c3e50; color: white; position: sticky; top: 0; }\n\
+         tr.added { background: #e6ffed; }\n\
+         tr.removed { background: #ffeef0; }\n\
+         tr.changed_old, tr.changed_new { background: #fff8c5; }\n\
+         td.cell-changed { background: #ffd33d; font-weight: bold; }\n\
+         </style></head><body>\n",
+    );
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(title)));
+    html.push_str(&format!(
+        "<p>{} added, {} removed, {} changed</p>\n",
+        count("added"),
+        count("removed"),
+        count("changed_new")
+    ));
+    for (status, columns) in drift {
+        if !columns.is_empty() {
+            let names: Vec<String> = columns
+                .iter()
+                .map(|c| escape_html(&localize_header(c, header_lang)))
+                .collect();
+            html.push_str(&format!("<p>{}: {}</p>\n", status, names.join(", ")));
+        }
+    }
 
+    html.push_str("<table>\n<tr>");
+    for header in localize_headers(headers, header_lang) {
+        html.push_str(&format!("<th>{}</th>", escape_html(&header)));
+    }
+    html.push_str("</tr>\n");
+    for (i, (status, row)) in diff_rows.iter().enumerate() {
+        let other = partner.get(&i).map(|&p| &diff_rows[p].1);
+        html.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td>",
+            status,
+            escape_html(status)
+        ));
+        for (c, cell) in row.iter().enumerate() {
+            let changed = other.is_some_and(|o| o.get(c) != Some(cell));
+            html.push_str(&format!(
+                "<td{}>{}</td>",
+                if changed {
+                    " class=\"cell-changed\""
+                } else {
+                    ""
+                },
+                escape_html(cell)
+            ));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body></html>\n");
+    fs::write(filename, html)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::DateFormat;
+
+    /// Filenames stamped in either `--date-format` parse back to the same
+    /// date, with or without a compression suffix.
+    #[test]
+    fn filename_date_round_trips_both_formats() {
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        for (format, expected) in [
+            (DateFormat::Swiss, "swissdamed_05.03.2025.csv"),
+            (DateFormat::Iso, "swissdamed_2025-03-05.csv"),
+        ] {
+            let name = format!("swissdamed_{}.csv", format.stamp(date));
+            assert_eq!(name, expected);
+            assert_eq!(filename_date(Path::new(&name)), Some(date));
+            let gz = format!("{}.gz", name);
+            assert_eq!(filename_date(Path::new(&gz)), Some(date));
+        }
+        assert_eq!(
+            filename_date(Path::new("2025-03-05_swissdamed.csv")),
+            Some(date)
+        );
+        assert_eq!(filename_date(Path::new("swissdamed_latest.csv")), None);
+        assert_eq!(filename_date(Path::new("swissdamed_2025.03.05.csv")), None);
+
+        // `{date}/{name}.{ext}` puts the date in a folder the template adds
+        let nested = Path::new("csv/2025-03-05/swissdamed.csv");
+        assert_eq!(path_date(nested, 1), Some(date));
+        assert_eq!(path_date(nested, 0), None);
+        assert_eq!(
+            path_date(Path::new("2025-03-05/csv/swissdamed.csv"), 1),
+            None
+        );
+        assert_eq!(
+            path_date(Path::new("2024-01-01/swissdamed_05.03.2025.csv"), 1),
+            Some(date),
+            "the file name wins"
+        );
+    }
+
+    /// The header line decides the delimiter; quoted cells, later lines
+    /// and ties fall back as documented.
+    #[test]
+    fn detect_delimiter_reads_the_header_line() {
+        assert_eq!(detect_delimiter(b"a;b;c\n1,2,3,4,5", b','), b';');
+        assert_eq!(detect_delimiter(b"a\tb\tc\r\n1;2", b','), b'\t');
+        assert_eq!(detect_delimiter(b"\"x;y;z\",b\n", b';'), b',');
+        assert_eq!(
+            detect_delimiter(b"a;b,c\n", b','),
+            b',',
+            "tie keeps fallback"
+        );
+        assert_eq!(
+            detect_delimiter(b"a;b,c\n", b'\t'),
+            b',',
+            "tie, comma first"
+        );
+        assert_eq!(detect_delimiter(b"udiDiCode\n1", b';'), b';');
+        assert_eq!(detect_delimiter(b"", b','), b',');
+    }
+
+    /// CSVs written with LF or CRLF record terminators (and line breaks
+    /// inside quoted cells) read back to the same table.
+    #[test]
+    fn read_csv_rows_accepts_lf_and_crlf() {
+        let headers = vec!["udiDiCode".to_string(), "deviceName".to_string()];
+        let rows = vec![
+            vec!["07640000000011".to_string(), "Pflaster\nsteril".to_string()],
+            vec!["07640000000028".to_string(), "Binde".to_string()],
+        ];
+        let path = std::env::temp_dir().join(format!(
+            "swissdamed2sqlite_crlf_test_{}.csv",
+            std::process::id()
+        ));
+        for crlf in [false, true] {
+            let opts = CsvOptions {
+                crlf,
+                ..CsvOptions::default()
+            };
+            let written =
+                crate::export::write_csv_with(&headers, &rows, &path.to_string_lossy(), &opts)
+                    .unwrap();
+            let raw = fs::read(&written).unwrap();
+            assert_eq!(raw.windows(2).any(|w| w == b"\r\n"), crlf);
+            let table = read_csv_rows(Path::new(&written), &opts).unwrap();
+            fs::remove_file(&written).ok();
+            assert_eq!(table, (headers.clone(), rows.clone()), "crlf: {}", crlf);
+        }
+    }
+
+    /// `--diff` refuses to compare a technical-header CSV with a
+    /// `--header-lang de` one instead of reporting every column as changed.
+    #[test]
+    fn diff_rejects_mixed_header_languages() {
+        let headers = vec!["udiDiCode".to_string(), "deviceName".to_string()];
+        let rows = vec![vec!["07640000000011".to_string(), "Pflaster".to_string()]];
+        let write = |lang: HeaderLang, suffix: &str| {
+            let path = std::env::temp_dir().join(format!(
+                "swissdamed2sqlite_header_lang_test_{}_{}.csv",
+                std::process::id(),
+                suffix
+            ));
+            let opts = CsvOptions {
+                header_lang: lang,
+                ..CsvOptions::default()
+            };
+            crate::export::write_csv_with(&headers, &rows, &path.to_string_lossy(), &opts).unwrap()
+        };
+        let old = write(HeaderLang::Technical, "old");
+        let new = write(HeaderLang::De, "new");
+        let result = diff_csv_files(Path::new(&old), Path::new(&new), &DiffOptions::default());
+        fs::remove_file(&old).ok();
+        fs::remove_file(&new).ok();
+        let err = result.expect_err("mixed header languages").to_string();
+        assert!(err.contains("different header languages"), "{}", err);
+    }
+
+    /// The HTML diff colors rows by status and highlights only the cells
+    /// that differ within a changed_old/changed_new pair.
+    #[test]
+    fn diff_html_highlights_changed_cells() {
+        let headers: Vec<String> = ["diff_status", "udiDiCode", "deviceName"]
+            .map(String::from)
+            .to_vec();
+        let row = |status: &str, code: &str, name: &str| {
+            (status.to_string(), vec![code.to_string(), name.to_string()])
+        };
+        let diff_rows = vec![
+            row("added", "07640000000011", "Pflaster"),
+            row("changed_old", "07640000000028", "Binde"),
+            row("changed_new", "07640000000028", "Binde <steril>"),
+        ];
+        let path = std::env::temp_dir().join(format!(
+            "swissdamed2sqlite_diff_html_test_{}.html",
+            std::process::id()
+        ));
+        let filename = path.to_string_lossy();
+        write_diff_html(
+            &filename,
+            "test",
+            &headers,
+            &[],
+            &diff_rows,
+            &[0],
+            HeaderLang::default(),
+        )
+        .unwrap();
+        let html = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert!(html.contains("<tr class=\"added\"><td>added</td><td>07640000000011</td>"));
+        assert!(html.contains("<td>07640000000028</td><td class=\"cell-changed\">Binde</td>"));
+        assert!(html.contains("<td class=\"cell-changed\">Binde &lt;steril&gt;</td>"));
+        assert_eq!(html.matches("class=\"cell-changed\"").count(), 2);
+    }
+}
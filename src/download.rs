@@ -1,52 +1,201 @@
+use log::{debug, info, warn};
 use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::progress;
 
 const BROWSER_USER_AGENT: &str =
     "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/140.0.0.0 Safari/537.36";
 
-/// Create a reqwest blocking client with cookie store and browser-like User-Agent.
-pub fn http_client() -> Result<reqwest::blocking::Client, Box<dyn std::error::Error>> {
-    Ok(reqwest::blocking::Client::builder()
+/// Default endpoint for the paginated UDI product list.
+pub const UDI_BASE_URL: &str = "https://swissdamed.ch/public/udi/basic-udis";
+
+/// API page size unless `--page-size` says otherwise (also used by the GUI).
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Timeout for the single-request `--count` probe.
+const COUNT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often one request is retried after `429 Too Many Requests`.
+const RATE_LIMIT_RETRIES: u32 = 5;
+
+/// Wait after a 429 without a usable `Retry-After` header.
+const RATE_LIMIT_DEFAULT_WAIT: Duration = Duration::from_secs(30);
+
+/// Upper bound on a single `Retry-After` wait.
+const RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(600);
+
+/// Client builder with cookie store and browser-like User-Agent.
+fn http_client_builder() -> reqwest::blocking::ClientBuilder {
+    reqwest::blocking::Client::builder()
         .cookie_store(true)
         .user_agent(BROWSER_USER_AGENT)
-        .build()?)
+}
+
+/// Create a reqwest blocking client with cookie store and browser-like User-Agent.
+pub fn http_client() -> Result<reqwest::blocking::Client, Box<dyn std::error::Error>> {
+    Ok(http_client_builder().build()?)
 }
 
 pub fn download_all_pages(page_size: u32) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
-    download_all_pages_from(
-        "https://swissdamed.ch/public/udi/basic-udis",
-        "UDI",
-        page_size,
+    download_all_pages_from(UDI_BASE_URL, "UDI", page_size)
+}
+
+/// Read the total element count from a paginated response body
+/// (`totalElements`, falling back to `total` / `totalCount`).
+fn total_count(body: &Value) -> Option<u64> {
+    ["totalElements", "total", "totalCount"]
+        .iter()
+        .find_map(|key| body.get(*key).and_then(|v| v.as_u64()))
+}
+
+/// Parse a `Retry-After` value: delay seconds or an HTTP-date (RFC 2822
+/// form, e.g. `Wed, 21 Oct 2026 07:28:00 GMT`), relative to `now`. A date
+/// in the past means no wait.
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
     )
 }
 
+/// POST the `{}` body the list endpoints expect. On `429 Too Many
+/// Requests` sleep for the server's `Retry-After` (capped at
+/// [`RATE_LIMIT_MAX_WAIT`]) and retry the same URL, up to
+/// [`RATE_LIMIT_RETRIES`] times; any other status is returned as is.
+fn post_list_page(
+    client: &reqwest::blocking::Client,
+    url: &str,
+) -> Result<reqwest::blocking::Response, Box<dyn std::error::Error>> {
+    let mut retries = 0;
+    loop {
+        let resp = client
+            .post(url)
+            .header("Accept", "application/json, text/plain, */*")
+            .header("Content-Type", "application/json")
+            .body("{}")
+            .send()?;
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS || retries == RATE_LIMIT_RETRIES
+        {
+            return Ok(resp);
+        }
+        retries += 1;
+        let wait = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, chrono::Utc::now()))
+            .unwrap_or(RATE_LIMIT_DEFAULT_WAIT)
+            .min(RATE_LIMIT_MAX_WAIT);
+        warn!(
+            "HTTP 429 for {}: retry {}/{} after {}s ...",
+            url,
+            retries,
+            RATE_LIMIT_RETRIES,
+            wait.as_secs()
+        );
+        std::thread::sleep(wait);
+    }
+}
+
+/// Number of pages the API reports for `page_size`: `totalPages` if
+/// present, otherwise derived from the total element count.
+fn total_pages(body: &Value, page_size: u32) -> Option<u64> {
+    body.get("totalPages")
+        .and_then(|v| v.as_u64())
+        .or_else(|| total_count(body).map(|t| t.div_ceil(page_size.max(1) as u64)))
+}
+
+/// Fetch only the first page (size 1) and return the dataset size reported
+/// by the API, without downloading the items themselves.
+pub fn fetch_total_count(base_url: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let client = http_client_builder().timeout(COUNT_TIMEOUT).build()?;
+
+    let url = format!("{}?page=0&size=1", base_url);
+    let resp = post_list_page(&client, &url)?;
+
+    if !resp.status().is_success() {
+        return Err(format!("HTTP error: {} for {}", resp.status(), url).into());
+    }
+
+    let body: Value = resp.json()?;
+    total_count(&body).ok_or_else(|| "Response missing a total element count".into())
+}
+
+/// Download every page of a paginated swissdamed list. When the first page
+/// reports `totalPages`/`totalElements` the loop runs to that last page;
+/// otherwise it stops at the first empty or short page. Progress is a bar
+/// on an interactive stderr and per-page info lines otherwise (which
+/// `--quiet` filters out with the rest of the info log).
 pub fn download_all_pages_from(
     base_url: &str,
     label: &str,
     page_size: u32,
 ) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    download_pages_from(base_url, label, page_size, None)
+}
+
+/// Like [`download_all_pages_from`], stopping once `limit` items were
+/// collected (`--limit`); the last page is truncated and pages are no
+/// larger than the limit.
+pub fn download_pages_from(
+    base_url: &str,
+    label: &str,
+    page_size: u32,
+    limit: Option<usize>,
+) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let page_size = match limit {
+        Some(n) => page_size.min(n.try_into().unwrap_or(u32::MAX)).max(1),
+        None => page_size,
+    };
     let client = http_client()?;
+    let log_pages = !progress::interactive();
+    let mut bar = None;
+    let mut last_page = None;
 
     let mut all_values: Vec<Value> = Vec::new();
     let mut page: u32 = 0;
 
     loop {
         let url = format!("{}?page={}&size={}", base_url, page, page_size);
-        eprintln!("[{}] Fetching page {} ...", label, page);
+        if log_pages {
+            info!("[{}] Fetching page {} ...", label, page);
+        }
 
-        let resp = client
-            .post(&url)
-            .header("Accept", "application/json, text/plain, */*")
-            .header("Content-Type", "application/json")
-            .body("{}")
-            .send()?;
+        let resp = post_list_page(&client, &url)?;
 
+        debug!(
+            "[{}] page {}: HTTP {} from {}",
+            label,
+            page,
+            resp.status(),
+            url
+        );
         if !resp.status().is_success() {
             return Err(format!("HTTP error: {} for page {}", resp.status(), page).into());
         }
 
         let body: Value = resp.json()?;
+        if page == 0 {
+            let total = total_count(&body).map(|t| match limit {
+                Some(n) => t.min(n as u64),
+                None => t,
+            });
+            bar = progress::bar(label, "items", total);
+            last_page = total_pages(&body, page_size);
+            if let Some(pages) = last_page {
+                debug!("[{}] API reports {} pages", label, pages);
+            }
+        }
 
         let values = body
             .get("values")
@@ -57,23 +206,37 @@ pub fn download_all_pages_from(
             break;
         }
 
-        let count = values.len();
-        all_values.extend(values.iter().cloned());
-        eprintln!(
-            "[{}]   got {} items (total so far: {})",
-            label,
-            count,
-            all_values.len()
-        );
+        let remaining = limit.map_or(usize::MAX, |n| n - all_values.len());
+        let count = values.len().min(remaining);
+        all_values.extend(values.iter().take(count).cloned());
+        if let Some(pb) = &bar {
+            pb.inc(count as u64);
+            pb.set_message(format!("page {}", page + 1));
+        }
+        if log_pages {
+            info!(
+                "[{}]   got {} items (total so far: {})",
+                label,
+                count,
+                all_values.len()
+            );
+        }
 
-        if (count as u32) < page_size {
+        let at_end = match last_page {
+            Some(pages) => u64::from(page) + 1 >= pages,
+            None => (count as u32) < page_size,
+        };
+        if at_end || limit.is_some_and(|n| all_values.len() >= n) {
             break;
         }
 
         page += 1;
     }
 
-    eprintln!(
+    if let Some(pb) = bar {
+        pb.finish_and_clear();
+    }
+    info!(
         "[{}] Download complete: {} items total.",
         label,
         all_values.len()
@@ -81,15 +244,561 @@ pub fn download_all_pages_from(
     Ok(all_values)
 }
 
-pub fn load_json_file(path: &PathBuf) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(path)?;
-    let parsed: Value = serde_json::from_str(&content)?;
+/// Write downloaded items as `{"values": [...]}`, the layout `--file` reads
+/// back.
+pub fn save_json_file(path: &Path, values: &[Value]) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let file =
+        fs::File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    #[derive(serde::Serialize)]
+    struct Dump<'a> {
+        values: &'a [Value],
+    }
+    serde_json::to_writer(&mut writer, &Dump { values })?;
+    std::io::Write::flush(&mut writer)?;
+    Ok(())
+}
+
+/// Layout of an input dump given with `--file`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    /// NDJSON for `.ndjson`/`.jsonl` files, a single JSON document otherwise
+    #[default]
+    Auto,
+    /// One JSON document: `{"values": [...]}` or a top-level array
+    Json,
+    /// Newline-delimited JSON, one item per line
+    Ndjson,
+}
+
+/// True for `*.ndjson` / `*.jsonl` paths (optionally with a trailing `.gz`).
+fn is_ndjson_path(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    name.ends_with(".ndjson") || name.ends_with(".jsonl")
+}
+
+/// Load items from a JSON dump (`{"values": [...]}` or a top-level array).
+/// Gzip-compressed files are detected by a `.gz` extension or the gzip magic
+/// bytes and decompressed transparently.
+pub fn load_json_file(path: &Path) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    load_json_file_with(path, InputFormat::Auto)
+}
+
+/// Like [`load_json_file`] with an explicit [`InputFormat`]; NDJSON files are
+/// parsed line by line, skipping blank lines. The path `-` reads stdin to EOF;
+/// an empty stdin yields no items.
+pub fn load_json_file_with(
+    path: &Path,
+    format: InputFormat,
+) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let mut values = Vec::new();
+    for_each_item(path, format, &mut |item| {
+        values.push(item);
+        Ok(())
+    })?;
+    Ok(values)
+}
+
+/// Load and concatenate several dumps in order, reporting per-file item
+/// counts so an empty shard stands out. Reading stops after `limit` items
+/// (`--limit`).
+pub fn load_json_files(
+    paths: &[PathBuf],
+    format: InputFormat,
+    limit: Option<usize>,
+) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let mut all_values = Vec::new();
+    for_each_item_in_files(paths, format, limit, &mut |item| {
+        all_values.push(item);
+        Ok(())
+    })?;
+    Ok(all_values)
+}
+
+/// Callback receiving one input item at a time.
+pub type ItemSink<'a> = dyn FnMut(Value) -> Result<(), Box<dyn std::error::Error>> + 'a;
+
+/// Returned by the sink in [`for_each_item_in_files`] once `limit` items
+/// were read; never escapes it.
+#[derive(Debug)]
+struct LimitReached;
+
+impl std::fmt::Display for LimitReached {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("item limit reached")
+    }
+}
+
+impl std::error::Error for LimitReached {}
 
-    if let Some(arr) = parsed.get("values").and_then(|v| v.as_array()) {
-        Ok(arr.clone())
-    } else if let Some(arr) = parsed.as_array() {
-        Ok(arr.clone())
+/// Streaming counterpart of [`load_json_files`]: items of every file are
+/// handed to `f` as they are parsed, so only one item is in memory at a time.
+/// Directories are expanded to their `*.json` files (see [`expand_input_paths`]).
+/// With a `limit`, parsing stops once that many items were handed over.
+pub fn for_each_item_in_files(
+    paths: &[PathBuf],
+    format: InputFormat,
+    limit: Option<usize>,
+    f: &mut ItemSink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let paths = &expand_input_paths(paths)?;
+    let mut total = 0usize;
+    for path in paths {
+        if limit.is_some_and(|n| total >= n) {
+            break;
+        }
+        if is_stdin_path(path) {
+            info!("Loading from stdin");
+        } else {
+            info!("Loading from file: {}", path.display());
+        }
+        let mut count = 0usize;
+        let result = for_each_item(path, format, &mut |item| {
+            count += 1;
+            f(item)?;
+            if limit.is_some_and(|n| total + count >= n) {
+                return Err(Box::new(LimitReached));
+            }
+            Ok(())
+        });
+        match result {
+            Err(e) if e.is::<LimitReached>() => {}
+            other => other?,
+        }
+        if paths.len() > 1 {
+            info!("  {} items", count);
+        }
+        total += count;
+    }
+    if paths.len() > 1 {
+        info!("Merged {} files: {} items total.", paths.len(), total);
+    }
+    if let Some(n) = limit.filter(|&n| total >= n) {
+        info!("--limit: stopped reading after {} items.", n);
+    }
+    Ok(())
+}
+
+/// Replace each directory in `paths` by the `*.json` files directly inside
+/// it, in lexical order (e.g. the `page_0000.json` ... dumps of a resumable
+/// download). Other paths are kept as given.
+pub fn expand_input_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            expanded.push(path.clone());
+            continue;
+        }
+        let mut files: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|e| format!("failed to read directory {}: {}", path.display(), e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.is_file()
+                    && p.extension()
+                        .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+            })
+            .collect();
+        if files.is_empty() {
+            return Err(format!("No *.json files found in {}", path.display()).into());
+        }
+        files.sort();
+        info!("Directory {}: {} JSON files", path.display(), files.len());
+        expanded.extend(files);
+    }
+    Ok(expanded)
+}
+
+/// Stream the items of a single dump (see [`load_json_file_with`]) into `f`.
+pub fn for_each_item(
+    path: &Path,
+    format: InputFormat,
+    f: &mut ItemSink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+    {
+        return for_each_item_in_zip(path, format, f);
+    }
+
+    let from_stdin = is_stdin_path(path);
+    let label = if from_stdin {
+        "stdin".to_string()
+    } else {
+        path.display().to_string()
+    };
+    let mut reader: Box<dyn BufRead> = if from_stdin {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(fs::File::open(path)?))
+    };
+
+    let head = reader
+        .fill_buf()
+        .map_err(|e| format!("failed to read {}: {}", label, e))?;
+    if from_stdin && head.is_empty() {
+        return Ok(());
+    }
+    let is_gzip = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+        || head.starts_with(&[0x1f, 0x8b]);
+    if is_gzip {
+        reader = Box::new(BufReader::new(flate2::read::GzDecoder::new(reader)));
+    }
+
+    let ndjson = match format {
+        InputFormat::Auto => is_ndjson_path(path),
+        InputFormat::Json => false,
+        InputFormat::Ndjson => true,
+    };
+    let input = StreamInput {
+        label: &label,
+        is_gzip,
+        allow_empty: from_stdin,
+    };
+    if ndjson {
+        stream_ndjson(reader, &input, f)
     } else {
-        Err("JSON must contain a 'values' array or be a top-level array".into())
+        stream_values(reader, &input, f)
+    }
+}
+
+/// Stream the single `*.json` entry of a ZIP archive (macOS `__MACOSX/`
+/// metadata entries are ignored).
+fn for_each_item_in_zip(
+    path: &Path,
+    format: InputFormat,
+    f: &mut ItemSink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)
+        .map_err(|e| format!("{} is not a valid ZIP archive: {}", path.display(), e))?;
+    let names: Vec<String> = archive
+        .file_names()
+        .filter(|n| !n.starts_with("__MACOSX/") && n.to_lowercase().ends_with(".json"))
+        .map(String::from)
+        .collect();
+    let name = match names.as_slice() {
+        [name] => name.clone(),
+        [] => return Err(format!("{} contains no *.json file", path.display()).into()),
+        _ => {
+            return Err(format!(
+                "{} contains {} *.json files ({}); expected exactly one",
+                path.display(),
+                names.len(),
+                names.join(", ")
+            )
+            .into())
+        }
+    };
+
+    let label = format!("{}:{}", path.display(), name);
+    let reader = BufReader::new(archive.by_name(&name)?);
+    let input = StreamInput {
+        label: &label,
+        is_gzip: false,
+        allow_empty: false,
+    };
+    if format == InputFormat::Ndjson {
+        stream_ndjson(reader, &input, f)
+    } else {
+        stream_values(reader, &input, f)
+    }
+}
+
+/// True for the conventional `-` path meaning stdin.
+pub fn is_stdin_path(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Where a stream comes from, for error messages.
+struct StreamInput<'a> {
+    label: &'a str,
+    is_gzip: bool,
+    /// Whitespace-only input yields no items instead of an error (stdin).
+    allow_empty: bool,
+}
+
+impl StreamInput<'_> {
+    fn io_error(&self, e: impl std::fmt::Display) -> Box<dyn std::error::Error> {
+        if self.is_gzip {
+            format!("{} is not valid gzip: {}", self.label, e).into()
+        } else {
+            format!("failed to read {}: {}", self.label, e).into()
+        }
+    }
+}
+
+/// Parse newline-delimited JSON (one item per line, blank lines skipped).
+fn stream_ndjson(
+    reader: impl BufRead,
+    input: &StreamInput,
+    f: &mut ItemSink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| {
+            if !input.is_gzip && e.kind() == std::io::ErrorKind::InvalidData {
+                format!("{} is not valid UTF-8: {}", input.label, e).into()
+            } else {
+                input.io_error(e)
+            }
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let item = serde_json::from_str(&line)
+            .map_err(|e| format!("{}: line {}: not valid JSON: {}", input.label, i + 1, e))?;
+        f(item)?;
+    }
+    Ok(())
+}
+
+/// Parse a JSON document, handing each element of its `values` array (or of
+/// the top-level array) to `f` without building the whole document.
+fn stream_values(
+    reader: impl BufRead,
+    input: &StreamInput,
+    f: &mut ItemSink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut visitor = ValuesVisitor {
+        f,
+        err: None,
+        started: false,
+    };
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let result = serde::Deserializer::deserialize_any(&mut de, &mut visitor).and_then(|_| de.end());
+
+    if let Some(e) = visitor.err {
+        return Err(e);
+    }
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) if e.is_eof() && !visitor.started && input.allow_empty => Ok(()),
+        Err(e) if e.is_io() => Err(input.io_error(e)),
+        Err(e) if e.is_data() => Err(format!("{}: {}", input.label, e).into()),
+        Err(e) => Err(format!("{}: not valid JSON: {}", input.label, e).into()),
+    }
+}
+
+const MISSING_VALUES: &str = "JSON must contain a 'values' array or be a top-level array";
+
+/// Serde visitor for the document root: a top-level array, or an object whose
+/// `values` member is the item array (other members are skipped).
+struct ValuesVisitor<'f, 'a> {
+    f: &'f mut ItemSink<'a>,
+    /// Error returned by `f`, kept so it isn't reported as a JSON error.
+    err: Option<Box<dyn std::error::Error>>,
+    started: bool,
+}
+
+impl<'de> serde::de::Visitor<'de> for &mut ValuesVisitor<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(MISSING_VALUES)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        self.started = true;
+        while let Some(item) = seq.next_element::<Value>()? {
+            if let Err(e) = (self.f)(item) {
+                self.err = Some(e);
+                return Err(serde::de::Error::custom("aborted"));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        self.started = true;
+        let mut found = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "values" && !found {
+                map.next_value_seed(ValuesArray(&mut *self))?;
+                found = true;
+            } else {
+                map.next_value::<serde::de::IgnoredAny>()?;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(serde::de::Error::custom(MISSING_VALUES))
+        }
+    }
+}
+
+/// Seed for the `values` member: must be an array.
+struct ValuesArray<'v, 'f, 'a>(&'v mut ValuesVisitor<'f, 'a>);
+
+impl<'de> serde::de::DeserializeSeed<'de> for ValuesArray<'_, '_, '_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn parse_retry_after_seconds_and_date() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn total_pages_prefers_total_pages_field() {
+        use serde_json::json;
+
+        assert_eq!(
+            total_pages(&json!({"totalPages": 3, "totalElements": 500}), 100),
+            Some(3)
+        );
+        assert_eq!(total_pages(&json!({"totalElements": 200}), 100), Some(2));
+        assert_eq!(total_pages(&json!({"totalElements": 201}), 100), Some(3));
+        assert_eq!(total_pages(&json!({"totalElements": 0}), 100), Some(0));
+        assert_eq!(total_pages(&json!({"values": []}), 100), None);
+    }
+
+    /// A gzipped dump must load to exactly the same items as the plain file.
+    #[test]
+    fn load_gzip_json() {
+        let plain = load_json_file(&fixture("udi_sample.json")).expect("plain fixture");
+        let gz = load_json_file(&fixture("udi_sample.json.gz")).expect("gzip fixture");
+        assert_eq!(plain.len(), 2);
+        assert_eq!(plain, gz);
+    }
+
+    /// A zipped dump loads to the same items as the plain file.
+    #[test]
+    fn load_zip_json() {
+        let plain = load_json_file(&fixture("udi_sample.json")).expect("plain fixture");
+        let zipped = load_json_file(&fixture("udi_sample.zip")).expect("zip fixture");
+        assert_eq!(plain, zipped);
+    }
+
+    /// Corrupt gzip and non-JSON content report distinct errors.
+    #[test]
+    fn gzip_and_json_errors_differ() {
+        let dir = std::env::temp_dir();
+        let bad_gz = dir.join("swissdamed2sqlite_bad.json.gz");
+        fs::write(&bad_gz, b"\x1f\x8bnot really gzip").unwrap();
+        let err = load_json_file(&bad_gz).unwrap_err().to_string();
+        assert!(err.contains("not valid gzip"), "{}", err);
+
+        let bad_json = dir.join("swissdamed2sqlite_bad.json");
+        fs::write(&bad_json, b"{ values: ").unwrap();
+        let err = load_json_file(&bad_json).unwrap_err().to_string();
+        assert!(err.contains("not valid JSON"), "{}", err);
+
+        let _ = fs::remove_file(bad_gz);
+        let _ = fs::remove_file(bad_json);
+    }
+
+    /// A large dump is parsed item by item: each item reaches the callback
+    /// after only its own bytes (plus one read buffer) were consumed, so the
+    /// whole document is never materialized.
+    #[test]
+    fn streams_large_dump_item_by_item() {
+        use std::cell::Cell;
+        use std::io::{Read, Write};
+        use std::rc::Rc;
+
+        struct CountingReader<R> {
+            inner: R,
+            consumed: Rc<Cell<usize>>,
+        }
+        impl<R: Read> Read for CountingReader<R> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.inner.read(buf)?;
+                self.consumed.set(self.consumed.get() + n);
+                Ok(n)
+            }
+        }
+
+        const ITEMS: usize = 50_000;
+        const SLACK: usize = 64 * 1024;
+        let item = |i: usize| {
+            format!(
+                r#"{{"basicUdi":"B{:08}","deviceName":"{}","udiDis":[{{"udiDiCode":"{:014}"}}]}}"#,
+                i,
+                "x".repeat(200),
+                i
+            )
+        };
+        let item_len = item(0).len() + 1;
+
+        let path = std::env::temp_dir().join("swissdamed2sqlite_large.json");
+        {
+            let mut w = std::io::BufWriter::new(fs::File::create(&path).unwrap());
+            write!(w, r#"{{"total":{},"values":["#, ITEMS).unwrap();
+            for i in 0..ITEMS {
+                if i > 0 {
+                    w.write_all(b",").unwrap();
+                }
+                w.write_all(item(i).as_bytes()).unwrap();
+            }
+            w.write_all(b"]}").unwrap();
+        }
+        let size = fs::metadata(&path).unwrap().len() as usize;
+        assert!(size > ITEMS * 200);
+
+        let consumed = Rc::new(Cell::new(0));
+        let reader = BufReader::new(CountingReader {
+            inner: fs::File::open(&path).unwrap(),
+            consumed: consumed.clone(),
+        });
+        let input = StreamInput {
+            label: "large",
+            is_gzip: false,
+            allow_empty: false,
+        };
+        let mut seen = 0usize;
+        stream_values(reader, &input, &mut |value| {
+            assert_eq!(value["basicUdi"], format!("B{:08}", seen));
+            assert!(
+                consumed.get() <= (seen + 1) * item_len + SLACK,
+                "item {} arrived after reading {} of {} bytes",
+                seen,
+                consumed.get(),
+                size
+            );
+            seen += 1;
+            Ok(())
+        })
+        .expect("stream large dump");
+        assert_eq!(seen, ITEMS);
+
+        let _ = fs::remove_file(path);
     }
 }
@@ -1,4 +1,5 @@
 use chrono::Local;
+use log::info;
 use std::collections::HashSet;
 use std::fs;
 
@@ -48,7 +49,7 @@ pub fn is_valid_srn(srn: &str) -> bool {
         Some(pos) => pos,
         None => return false,
     };
-    if first_dash < 2 || first_dash > 3 {
+    if !(2..=3).contains(&first_dash) {
         return false;
     }
     if !upper[..first_dash].chars().all(|c| c.is_ascii_alphabetic()) {
@@ -83,8 +84,7 @@ pub fn write_srn_error_report(
         return Ok(None);
     }
 
-    let html_dir = crate::app_data_dir().join("html");
-    fs::create_dir_all(&html_dir)?;
+    let html_dir = crate::export::output_subdir("html")?;
     let timestamp = Local::now().format("%Hh%M.%d.%m.%Y").to_string();
     let html_path = html_dir
         .join(format!("srn_error_report_{}.html", timestamp))
@@ -149,6 +149,6 @@ pub fn write_srn_error_report(
 
     fs::write(&html_path, &html)?;
 
-    eprintln!("Error report written: {}", html_path);
+    info!("Error report written: {}", html_path);
     Ok(Some(html_path))
 }
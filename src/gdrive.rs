@@ -1,3 +1,4 @@
+use log::info;
 use serde_json::Value;
 use std::fs;
 use std::process::Command;
@@ -156,7 +157,7 @@ fn upload_to_gdrive(
     if resp.status().is_success() {
         let result: Value = resp.json()?;
         let id = result.get("id").and_then(|v| v.as_str()).unwrap_or("?");
-        eprintln!("Uploaded {} to Google Drive (id: {})", file_name, id);
+        info!("Uploaded {} to Google Drive (id: {})", file_name, id);
     } else {
         let status = resp.status();
         let err_body = resp.text().unwrap_or_default();
@@ -176,7 +177,7 @@ pub fn gdrive_upload_csv(args: &Args, csv_path: &str) -> Result<(), Box<dyn std:
     let gdrive_folder =
         resolve_setting(&args.gdrive_folder, &config.gdrive_folder, "gdrive-folder")?;
     let (pem, email) = resolve_google_credentials(args)?;
-    eprintln!("Uploading {} to Google Drive...", csv_path);
+    info!("Uploading {} to Google Drive...", csv_path);
     let token = get_google_access_token(
         &pem,
         &email,
@@ -203,7 +204,7 @@ pub fn send_email_with_attachment(
         .as_deref()
         .ok_or("--mailto requires --gdrive-sub <email> to send from")?;
 
-    eprintln!("Sending {} via email to {} ...", csv_path, to_email);
+    info!("Sending {} via email to {} ...", csv_path, to_email);
 
     let (pem, email) = resolve_google_credentials(args)?;
     let token = get_google_access_token(
@@ -280,7 +281,7 @@ pub fn send_email_with_attachment(
     if resp.status().is_success() {
         let result: Value = resp.json()?;
         let id = result.get("id").and_then(|v| v.as_str()).unwrap_or("?");
-        eprintln!("Email sent to {} (message id: {})", to_email, id);
+        info!("Email sent to {} (message id: {})", to_email, id);
     } else {
         let status = resp.status();
         let err_body = resp.text().unwrap_or_default();
@@ -339,7 +339,7 @@ fn run_products_pipeline(tx: mpsc::Sender<WorkerMsg>, ctx: egui::Context) {
 
     log("Downloading UDI products from swissdamed.ch ...");
 
-    let values = match crate::download::download_all_pages(50) {
+    let values = match crate::download::download_all_pages(crate::download::DEFAULT_PAGE_SIZE) {
         Ok(v) => v,
         Err(e) => {
             done(false, &format!("Download failed: {}", e));
@@ -388,7 +388,19 @@ fn run_products_pipeline(tx: mpsc::Sender<WorkerMsg>, ctx: egui::Context) {
             return;
         }
     };
-    match crate::export::write_sqlite(&headers, &rows, &db_path) {
+    let meta = crate::export::HarvestMeta {
+        source_url: Some(crate::download::UDI_BASE_URL.to_string()),
+        page_size: Some(crate::download::DEFAULT_PAGE_SIZE),
+        item_count: Some(values.len()),
+        ..Default::default()
+    };
+    match crate::export::write_sqlite_with(
+        &headers,
+        &rows,
+        &db_path,
+        &meta,
+        &crate::export::SqliteOptions::default(),
+    ) {
         Ok(()) => log(&format!("SQLite written: {}", db_path)),
         Err(e) => {
             done(false, &format!("SQLite write failed: {}", e));
@@ -623,7 +635,7 @@ fn run_migel_pipeline(tx: mpsc::Sender<WorkerMsg>, ctx: egui::Context) {
 
     // 1. Download UDI data
     log("Downloading UDI products from swissdamed.ch ...");
-    let values = match crate::download::download_all_pages(50) {
+    let values = match crate::download::download_all_pages(crate::download::DEFAULT_PAGE_SIZE) {
         Ok(v) => v,
         Err(e) => {
             done(false, &format!("Download failed: {}", e));
@@ -836,7 +848,26 @@ fn run_migel_pipeline(tx: mpsc::Sender<WorkerMsg>, ctx: egui::Context) {
             return;
         }
     };
-    match crate::export::write_sqlite(&migel_headers, &matched_rows, &db_path) {
+    let meta = crate::export::HarvestMeta {
+        source_url: Some(crate::download::UDI_BASE_URL.to_string()),
+        page_size: Some(crate::download::DEFAULT_PAGE_SIZE),
+        item_count: Some(values.len()),
+        migel: Some(crate::export::MigelMeta {
+            source: migel_url.to_string(),
+            items: migel_items.len(),
+            rows: rows.len(),
+            matched: matched_rows.len(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    match crate::export::write_sqlite_with(
+        &migel_headers,
+        &matched_rows,
+        &db_path,
+        &meta,
+        &crate::export::SqliteOptions::default(),
+    ) {
         Ok(()) => log(&format!("SQLite written: {}", db_path)),
         Err(e) => {
             done(false, &format!("SQLite write failed: {}", e));
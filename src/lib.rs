@@ -0,0 +1,78 @@
+//! Swiss DAMED (swissdamed.ch) UDI data → flat tables, as a library.
+//!
+//! The `swissdamed2sqlite` binary is a thin CLI/GUI over these modules:
+//!
+//! - [`download`]: paged API download and JSON/NDJSON file input,
+//! - [`data`]: flattening UDI items into headers and rows (one row per
+//!   udiDiCode, per-language `tradeName_<LANG>` columns),
+//! - [`export`]: CSV, SQLite, JSON/NDJSON, Parquet and XLSX writers,
+//! - [`diff`]: comparing two CSV exports,
+//! - [`migel`]: MiGeL list parsing and matching,
+//! - [`validate`]: data-quality report.
+//!
+//! ```
+//! let values: Vec<serde_json::Value> = serde_json::from_str(
+//!     r#"[{"basicUdi": "B1", "udiDis": [{"udiDiCode": "0764001",
+//!          "tradeNames": [{"language": "DE", "textValue": "Pflaster"}]}]}]"#,
+//! )
+//! .unwrap();
+//! let (headers, rows) = swissdamed2sqlite::convert_values(&values);
+//! assert_eq!(headers, ["basicUdi", "udiDiCode", "tradeName_DE"]);
+//! assert_eq!(rows, [["B1", "0764001", "Pflaster"]]);
+//! ```
+
+pub mod data;
+pub mod diff;
+pub mod download;
+pub mod export;
+pub mod manifest;
+pub mod migel;
+pub mod progress;
+pub mod validate;
+
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+const APP_DIR_NAME: &str = "swissdamed2sqlite";
+
+/// Return the application data directory (`~/swissdamed2sqlite/`).
+/// Under macOS App Sandbox, uses the container directory.
+/// On Windows uses `%USERPROFILE%\swissdamed2sqlite\`.
+/// Falls back to the current working directory.
+pub fn app_data_dir() -> PathBuf {
+    // macOS sandbox
+    if let Ok(container) = std::env::var("APP_SANDBOX_CONTAINER_ID") {
+        if !container.is_empty() {
+            if let Some(home) = std::env::var_os("HOME") {
+                let dir = PathBuf::from(home).join(APP_DIR_NAME);
+                let _ = fs::create_dir_all(&dir);
+                return dir;
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    let home = std::env::var_os("USERPROFILE");
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var_os("HOME");
+
+    if let Some(home) = home {
+        let dir = PathBuf::from(home).join(APP_DIR_NAME);
+        let _ = fs::create_dir_all(&dir);
+        return dir;
+    }
+
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// Convert UDI items (as returned by the API) into CSV-style headers and
+/// rows with the default cell formatting — what the CLI writes without
+/// options. Use [`data::collect_headers_with`] and [`data::build_rows_with`]
+/// for a custom [`data::ValueFormat`], and the [`export`] writers to store
+/// the result.
+pub fn convert_values(values: &[Value]) -> (Vec<String>, Vec<Vec<String>>) {
+    let (headers, trade_name_langs) = data::collect_headers(values);
+    let rows = data::build_rows(values, &headers, &trade_name_langs);
+    (headers, rows)
+}
@@ -7,6 +7,7 @@
 //! has a `refresh_token`, swap it for a fresh `access_token` before posting
 //! and persist the new token back to disk.
 
+use log::{info, warn};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -77,7 +78,7 @@ fn refresh_token(
     if token.refresh_token.is_empty() {
         return token.clone();
     }
-    eprintln!("[linkedin] Refreshing access token...");
+    info!("[linkedin] Refreshing access token...");
     let body = format!(
         "grant_type=refresh_token&refresh_token={}&client_id={}&client_secret={}",
         token.refresh_token, creds.client_id, creds.client_secret
@@ -88,7 +89,7 @@ fn refresh_token(
         .body(body)
         .send();
     let Ok(resp) = resp else {
-        eprintln!("[linkedin] Refresh request failed; using existing token");
+        warn!("[linkedin] Refresh request failed; using existing token");
         return token.clone();
     };
     let Ok(text) = resp.text() else {
@@ -98,7 +99,7 @@ fn refresh_token(
         return token.clone();
     };
     let Some(at) = data["access_token"].as_str().filter(|s| !s.is_empty()) else {
-        eprintln!("[linkedin] Refresh response had no access_token; using existing");
+        warn!("[linkedin] Refresh response had no access_token; using existing");
         return token.clone();
     };
     let new_token = Token {
@@ -111,47 +112,41 @@ fn refresh_token(
         expires_in: data["expires_in"].as_u64().unwrap_or(0),
     };
     if let Err(e) = save_token(token_path, &new_token) {
-        eprintln!("[linkedin] Could not persist refreshed token: {}", e);
+        warn!("[linkedin] Could not persist refreshed token: {}", e);
     } else {
-        eprintln!("[linkedin] Token refreshed");
+        info!("[linkedin] Token refreshed");
     }
     new_token
 }
 
 /// Read summary numbers from the latest MiGeL DB to build a post caption.
-fn build_caption(migel_db: &Path) -> String {
+fn build_caption(migel_db: &Path, migel_table: &str) -> String {
     let conn = match Connection::open(migel_db) {
         Ok(c) => c,
         Err(_) => return default_caption(),
     };
+    let table = crate::export::quote_ident(migel_table);
     let matched: i64 = conn
-        .query_row("SELECT COUNT(*) FROM swissdamed", [], |r| r.get(0))
+        .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |r| r.get(0))
         .unwrap_or(0);
     let codes: i64 = conn
         .query_row(
-            "SELECT COUNT(DISTINCT migel_code) FROM swissdamed",
+            &format!("SELECT COUNT(DISTINCT migel_code) FROM {table}"),
             [],
             |r| r.get(0),
         )
         .unwrap_or(0);
     let companies: i64 = conn
         .query_row(
-            "SELECT COUNT(DISTINCT companyName) FROM swissdamed",
+            &format!("SELECT COUNT(DISTINCT companyName) FROM {table}"),
             [],
             |r| r.get(0),
         )
         .unwrap_or(0);
-    let read_meta = |key: &str| -> i64 {
-        conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |r| {
-            r.get::<_, String>(0)
-        })
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(0)
-    };
-    let total = read_meta("total_products");
-    let override_matched = read_meta("override_matched");
-    let override_skipped = read_meta("override_skipped");
+    let read_meta = |key: &str| crate::export::read_meta_count(&conn, key).unwrap_or(0);
+    let total = read_meta("migel_rows");
+    let override_matched = read_meta("migel_override_matched");
+    let override_skipped = read_meta("migel_override_skipped");
     let heuristic = matched - override_matched;
     let pct = if total > 0 {
         format!("{:.1}%", matched as f64 / total as f64 * 100.0)
@@ -161,8 +156,8 @@ fn build_caption(migel_db: &Path) -> String {
 
     let top_companies: Vec<(String, i64)> = conn
         .prepare(
-            "SELECT companyName, COUNT(*) FROM swissdamed \
-             GROUP BY companyName ORDER BY 2 DESC LIMIT 3",
+            &format!("SELECT companyName, COUNT(*) FROM {table} \
+             GROUP BY companyName ORDER BY 2 DESC LIMIT 3"),
         )
         .ok()
         .and_then(|mut stmt| {
@@ -174,8 +169,8 @@ fn build_caption(migel_db: &Path) -> String {
 
     let top_categories: Vec<(String, i64)> = conn
         .prepare(
-            "SELECT migel_bezeichnung, COUNT(*) FROM swissdamed \
-             GROUP BY migel_code ORDER BY 2 DESC LIMIT 3",
+            &format!("SELECT migel_bezeichnung, COUNT(*) FROM {table} \
+             GROUP BY migel_code ORDER BY 2 DESC LIMIT 3"),
         )
         .ok()
         .and_then(|mut stmt| {
@@ -263,7 +258,7 @@ fn format_thousands(n: i64) -> String {
         out.push('-');
     }
     for (i, b) in bytes.iter().enumerate() {
-        if i > 0 && (bytes.len() - i) % 3 == 0 {
+        if i > 0 && (bytes.len() - i).is_multiple_of(3) {
             out.push('\'');
         }
         out.push(*b as char);
@@ -315,7 +310,7 @@ pub fn delete_post(post_ref: &str) -> Result<(), Box<dyn Error>> {
         .ok_or_else(|| format!("no 'urn:li:…' found in {:?}", post_ref))?;
 
     let (creds_path, creds) = load_credentials()?;
-    eprintln!("[linkedin] Using credentials: {}", creds_path.display());
+    info!("[linkedin] Using credentials: {}", creds_path.display());
     let (token_path, token) = load_token()?;
 
     let client = reqwest::blocking::Client::builder()
@@ -328,7 +323,7 @@ pub fn delete_post(post_ref: &str) -> Result<(), Box<dyn Error>> {
         "https://api.linkedin.com/rest/posts/{}",
         percent_encode(&urn)
     );
-    eprintln!("[linkedin] Deleting {}", urn);
+    info!("[linkedin] Deleting {}", urn);
     let resp = client
         .delete(&url)
         .header("Authorization", &auth)
@@ -337,7 +332,7 @@ pub fn delete_post(post_ref: &str) -> Result<(), Box<dyn Error>> {
         .send()?;
     let status = resp.status();
     if status.is_success() {
-        eprintln!("[linkedin] Deleted {}", urn);
+        info!("[linkedin] Deleted {}", urn);
         return Ok(());
     }
     let body = resp.text().unwrap_or_default();
@@ -345,9 +340,13 @@ pub fn delete_post(post_ref: &str) -> Result<(), Box<dyn Error>> {
 }
 
 /// Upload the given PNG to LinkedIn as an image post.
-pub fn publish_image(png_path: &Path, migel_db: &Path) -> Result<String, Box<dyn Error>> {
+pub fn publish_image(
+    png_path: &Path,
+    migel_db: &Path,
+    migel_table: &str,
+) -> Result<String, Box<dyn Error>> {
     let (creds_path, creds) = load_credentials()?;
-    eprintln!("[linkedin] Using credentials: {}", creds_path.display());
+    info!("[linkedin] Using credentials: {}", creds_path.display());
     let (token_path, token) = load_token()?;
     if token.person_id.is_empty() {
         return Err("linkedin_token.json has empty person_id (run li_push --auth)".into());
@@ -361,7 +360,7 @@ pub fn publish_image(png_path: &Path, migel_db: &Path) -> Result<String, Box<dyn
     let owner = format!("urn:li:person:{}", token.person_id);
     let auth = format!("Bearer {}", token.access_token);
     let bytes = fs::read(png_path)?;
-    eprintln!(
+    info!(
         "[linkedin] Uploading {} ({:.1} KB)",
         png_path.display(),
         bytes.len() as f64 / 1024.0
@@ -394,7 +393,7 @@ pub fn publish_image(png_path: &Path, migel_db: &Path) -> Result<String, Box<dyn
         .as_str()
         .ok_or_else(|| format!("no uploadUrl in initializeUpload response: {}", text))?
         .to_string();
-    eprintln!("[linkedin] Image URN: {}", image_urn);
+    info!("[linkedin] Image URN: {}", image_urn);
 
     // Step 2 — PUT the bytes
     let put_resp = client
@@ -408,10 +407,10 @@ pub fn publish_image(png_path: &Path, migel_db: &Path) -> Result<String, Box<dyn
         let body = put_resp.text().unwrap_or_default();
         return Err(format!("image PUT failed ({}): {}", put_status, body).into());
     }
-    eprintln!("[linkedin] Image bytes uploaded");
+    info!("[linkedin] Image bytes uploaded");
 
     // Step 3 — create post
-    let caption = build_caption(migel_db);
+    let caption = build_caption(migel_db, migel_table);
     let escaped = escape_little_text(&caption);
     let post_body = serde_json::json!({
         "author": owner,
@@ -451,6 +450,6 @@ pub fn publish_image(png_path: &Path, migel_db: &Path) -> Result<String, Box<dyn
         .unwrap_or("(unknown)")
         .to_string();
     let post_url = format!("https://www.linkedin.com/feed/update/{}/", post_id);
-    eprintln!("[linkedin] Published: {}", post_url);
+    info!("[linkedin] Published: {}", post_url);
     Ok(post_url)
 }
@@ -1,56 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-pub mod data;
-pub mod diff;
-pub mod download;
 mod error_report;
-pub mod export;
 pub mod gdrive;
 mod gui;
 pub mod linkedin;
-pub mod migel;
 mod migel_stats;
 pub mod reports;
 pub mod sigvaris_shop;
 pub mod twitter;
 
+// The conversion pipeline lives in the library crate; importing its modules
+// here keeps the `crate::data`, `crate::export`, ... paths of the CLI working.
+use swissdamed2sqlite::{
+    app_data_dir, data, diff, download, export, manifest, migel, progress, validate,
+};
+
 use clap::Parser;
+use log::{info, warn};
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
-const APP_DIR_NAME: &str = "swissdamed2sqlite";
-
-/// Return the application data directory (`~/swissdamed2sqlite/`).
-/// Under macOS App Sandbox, uses the container directory.
-/// On Windows uses `%USERPROFILE%\swissdamed2sqlite\`.
-/// Falls back to the current working directory.
-pub fn app_data_dir() -> PathBuf {
-    // macOS sandbox
-    if let Ok(container) = std::env::var("APP_SANDBOX_CONTAINER_ID") {
-        if !container.is_empty() {
-            if let Some(home) = std::env::var_os("HOME") {
-                let dir = PathBuf::from(home).join(APP_DIR_NAME);
-                let _ = fs::create_dir_all(&dir);
-                return dir;
-            }
-        }
-    }
-
-    #[cfg(target_os = "windows")]
-    let home = std::env::var_os("USERPROFILE");
-    #[cfg(not(target_os = "windows"))]
-    let home = std::env::var_os("HOME");
-
-    if let Some(home) = home {
-        let dir = PathBuf::from(home).join(APP_DIR_NAME);
-        let _ = fs::create_dir_all(&dir);
-        return dir;
-    }
-
-    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
-}
-
 /// Configuration loaded from config.toml in the app data directory.
 /// CLI arguments take precedence; config file provides fallback defaults.
 #[derive(serde::Deserialize, Default)]
@@ -67,7 +37,7 @@ impl Config {
         let path = app_data_dir().join("config.toml");
         match fs::read_to_string(&path) {
             Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
-                eprintln!("Warning: failed to parse {}: {}", path.display(), e);
+                warn!("failed to parse {}: {}", path.display(), e);
                 Config::default()
             }),
             Err(_) => Config::default(),
@@ -95,6 +65,138 @@ pub fn resolve_setting(
         })
 }
 
+/// Program used by `--deploy` to copy files to the remote server.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeployTool {
+    Scp,
+    Rsync,
+}
+
+/// Copy `filename` to `target` with the configured deploy tool and SSH
+/// options, then check the remote copy's SHA-256 unless `--skip-verify`.
+pub fn deploy_file(
+    args: &Args,
+    filename: &str,
+    target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tool = match args.deploy_tool {
+        DeployTool::Scp => "scp",
+        DeployTool::Rsync => "rsync",
+    };
+    let mut cmd = Command::new(tool);
+    match args.deploy_tool {
+        DeployTool::Scp => {
+            if let Some(key) = &args.ssh_identity {
+                cmd.arg("-i").arg(key);
+            }
+            if let Some(port) = args.ssh_port {
+                cmd.arg("-P").arg(port.to_string());
+            }
+        }
+        DeployTool::Rsync => {
+            cmd.arg("--checksum");
+            if args.ssh_identity.is_some() || args.ssh_port.is_some() {
+                // rsync splits -e on spaces itself: single quotes keep the
+                // key path one argument, a doubled quote is a literal one.
+                let mut ssh = String::from("ssh");
+                if let Some(key) = &args.ssh_identity {
+                    let key = key.to_string_lossy().replace('\'', "''");
+                    ssh.push_str(&format!(" -i '{}'", key));
+                }
+                if let Some(port) = args.ssh_port {
+                    ssh.push_str(&format!(" -p {}", port));
+                }
+                cmd.arg("-e").arg(ssh);
+            }
+        }
+    }
+    cmd.arg(filename).arg(target);
+
+    info!("Deploying {} to {} via {} ...", filename, target, tool);
+    let status = cmd
+        .status()
+        .map_err(|e| format!("Cannot run {}: {}", tool, e))?;
+    if !status.success() {
+        warn!("Deploy failed with exit code: {}", status);
+        return Err(format!("{} failed", tool).into());
+    }
+    info!("Deploy successful.");
+
+    if !args.skip_verify {
+        verify_deploy(args, filename, target)?;
+    }
+    Ok(())
+}
+
+/// Compare the SHA-256 of the local `filename` with `sha256sum` of the
+/// copy at `target` (`[user@]host:path`), run over ssh.
+fn verify_deploy(
+    args: &Args,
+    filename: &str,
+    target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use sha2::{Digest, Sha256};
+
+    let Some((host, path)) = target.split_once(':') else {
+        info!(
+            "Skipping checksum verification: {} is not a remote target.",
+            target
+        );
+        return Ok(());
+    };
+
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(filename)?, &mut hasher)?;
+    let local = format!("{:x}", hasher.finalize());
+
+    // The target may name a directory (with or without a trailing slash)
+    // or the file itself; let the remote shell decide.
+    let base = std::path::Path::new(filename)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "'\\''"));
+    let path = if path.is_empty() { "." } else { path };
+    let script = format!(
+        "f={}; [ -d \"$f\" ] && f=\"$f\"/{}; sha256sum \"$f\"",
+        quote(path),
+        quote(&base)
+    );
+
+    let mut ssh = Command::new("ssh");
+    if let Some(key) = &args.ssh_identity {
+        ssh.arg("-i").arg(key);
+    }
+    if let Some(port) = args.ssh_port {
+        ssh.arg("-p").arg(port.to_string());
+    }
+    let output = ssh
+        .arg(host)
+        .arg(script)
+        .output()
+        .map_err(|e| format!("Cannot run ssh for checksum verification: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Remote sha256sum failed ({}): {} (use --skip-verify if remote commands are unavailable)",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let remote = stdout.split_whitespace().next().unwrap_or("");
+    if remote != local {
+        return Err(format!(
+            "Checksum mismatch after deploy: local {} vs remote {}",
+            local,
+            if remote.is_empty() { "(none)" } else { remote }
+        )
+        .into());
+    }
+    info!("Checksum verified: {}", local);
+    Ok(())
+}
+
 /// Download Swiss DAMED UDI data and convert to CSV or SQLite
 #[derive(Parser, Debug)]
 #[command(name = "swissdamed2sqlite", version, about)]
@@ -103,19 +205,415 @@ pub struct Args {
     #[arg(long)]
     pub csv: bool,
 
+    /// Write CSV files (exports and diffs) without the UTF-8 BOM
+    #[arg(long)]
+    pub no_bom: bool,
+
+    /// Prefix CSV cells starting with =, +, -, @ or a tab with ' so Excel
+    /// shows them as text instead of evaluating a formula (plain numbers
+    /// are left alone; SQLite output is unaffected)
+    #[arg(long)]
+    pub safe_csv: bool,
+
+    /// End CSV records with CRLF (strict RFC 4180) instead of LF; also
+    /// applies to --diff output
+    #[arg(long)]
+    pub crlf: bool,
+
+    /// CSV field separator for exports and diffs (e.g. ';' for European
+    /// Excel, '\t' for tabs); CSVs read by --diff/--from-csv are
+    /// auto-detected, preferring this one
+    #[arg(long, default_value = ",", value_parser = export::parse_delimiter, value_name = "CHAR")]
+    pub delimiter: u8,
+
+    /// Language of the CSV/XLSX header row: technical names, or German
+    /// labels (udiDiCode -> "UDI-DI Code", companyName -> "Firma", ...);
+    /// SQLite, JSON and Parquet keep the technical names
+    #[arg(long, value_enum, default_value_t = export::HeaderLang::Technical, value_name = "LANG")]
+    pub header_lang: export::HeaderLang,
+
+    /// Compress outputs: CSV/JSON/NDJSON files get a .gz/.zst suffix, SQLite DBs
+    /// get a .db.gz/.db.zst copy next to them (which --deploy then uploads)
+    #[arg(long, value_enum, value_name = "ALGO")]
+    pub compress: Option<export::Compression>,
+
+    /// Shorthand for --compress gzip
+    #[arg(long, conflicts_with = "compress")]
+    pub gzip: bool,
+
+    /// Gzip compression level (0 = none, 9 = smallest; default 6); only
+    /// valid with gzip compression
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(0..=9))]
+    pub gzip_level: Option<u32>,
+
+    /// Also write one CSV per distinct value of this column (e.g.
+    /// companyName) into csv/by_<COLUMN>/ (or <--output dir>/by_<COLUMN>/)
+    #[arg(long, value_name = "COLUMN")]
+    pub split_by: Option<String>,
+
+    /// With --split-by, skip the combined CSV
+    #[arg(long, requires = "split_by")]
+    pub no_combined: bool,
+
+    /// Sort the output rows by these columns (comma-separated, e.g.
+    /// basicUdi,udiDiCode; bare flag: udiDiCode) so identical data gives
+    /// byte-identical files; empty values sort first
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "COLS",
+        num_args = 0..=1,
+        default_missing_value = "udiDiCode"
+    )]
+    pub sort_by: Vec<String>,
+
+    /// Date stamp in output filenames: swiss (DD.MM.YYYY) or iso
+    /// (YYYY-MM-DD, sorts chronologically); --diff reads both
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = export::DateFormat::Swiss)]
+    pub date_format: export::DateFormat,
+
     /// Output as SQLite database
     #[arg(long)]
     pub sqlite: bool,
 
-    /// Use an existing JSON file instead of downloading
+    /// Output as Excel workbook (xlsx/swissdamed_DD.MM.YYYY.xlsx) with a
+    /// bold, frozen header row; combinable with --csv/--sqlite
+    #[arg(long)]
+    pub xlsx: bool,
+
+    /// Output the flattened rows as a JSON array of objects
+    /// (json/swissdamed_DD.MM.YYYY.json); combinable with --csv/--sqlite
+    #[arg(long)]
+    pub json_out: bool,
+
+    /// Output the flattened rows as newline-delimited JSON, one object per
+    /// line (ndjson/swissdamed_DD.MM.YYYY.ndjson); empty tradeName_* keys
+    /// are omitted
+    #[arg(long)]
+    pub ndjson: bool,
+
+    /// Output the flattened rows as a snappy-compressed Parquet file
+    /// (parquet/swissdamed_DD.MM.YYYY.parquet), all columns UTF-8
+    #[arg(long)]
+    pub parquet: bool,
+
+    /// With --json-out, keep keys whose value is empty (omitted by default)
+    #[arg(long, requires = "json_out")]
+    pub keep_empty: bool,
+
+    /// With --json-out, pretty-print the JSON
+    #[arg(long, requires = "json_out")]
+    pub pretty: bool,
+
+    /// Use an existing JSON file instead of downloading (repeatable; the
+    /// items of all files are concatenated in order)
     #[arg(long, short = 'f')]
-    pub file: Option<PathBuf>,
+    pub file: Vec<PathBuf>,
+
+    /// Drop duplicate items by basicUdi, keeping the last occurrence
+    #[arg(long)]
+    pub dedup: bool,
+
+    /// Deduplicate on this field instead of basicUdi (implies --dedup)
+    #[arg(long, value_name = "FIELD")]
+    pub dedup_key: Option<String>,
+
+    /// Only keep items with this device status (repeatable,
+    /// case-insensitive; read from `status` or `deviceStatus`)
+    #[arg(long, value_name = "VALUE")]
+    pub status: Vec<String>,
+
+    /// Only keep items whose companyName contains this text
+    /// (repeatable, case-insensitive; `/.../` for a regex)
+    #[arg(long, value_name = "PATTERN")]
+    pub company: Vec<String>,
+
+    /// Only keep items of this risk class (repeatable; `Class IIa`,
+    /// `CLASS_IIA` and `IIa` are equivalent)
+    #[arg(long, value_name = "VALUE")]
+    pub risk_class: Vec<String>,
+
+    /// With --risk-class, also keep items that have no risk class
+    #[arg(long, requires = "risk_class")]
+    pub include_unknown_risk_class: bool,
+
+    /// Only output these columns, in this order (comma-separated;
+    /// udiDiCode and tradeName_* are appended unless listed or excluded)
+    #[arg(long, value_delimiter = ',', value_name = "COLS")]
+    pub columns: Vec<String>,
+
+    /// Drop these columns from the output (comma-separated)
+    #[arg(long, value_delimiter = ',', value_name = "COLS")]
+    pub exclude_columns: Vec<String>,
+
+    /// Take the UDI columns and their order from FILE (JSON array or one
+    /// name per line); data columns not listed are appended with a warning,
+    /// listed columns the data lacks are written empty
+    #[arg(long, value_name = "FILE", conflicts_with = "columns")]
+    pub schema: Option<PathBuf>,
+
+    /// With --schema, drop data columns the schema does not list
+    #[arg(long, requires = "schema")]
+    pub schema_strict: bool,
+
+    /// Write the run's UDI column list to FILE (one name per line) for
+    /// later use with --schema
+    #[arg(long, value_name = "FILE")]
+    pub write_schema: Option<PathBuf>,
+
+    /// Compare the columns discovered in the feed with the list in FILE
+    /// (same format as --write-schema), report added/removed columns and
+    /// store the new list in FILE once all outputs are written (not with
+    /// --dry-run or --print-schema); a missing FILE is created
+    #[arg(long, value_name = "FILE")]
+    pub schema_snapshot: Option<PathBuf>,
+
+    /// With --schema-snapshot, fail on added or removed columns (FILE is
+    /// then left unchanged, so the next run fails too until it is updated)
+    #[arg(long, requires = "schema_snapshot")]
+    pub fail_on_schema_change: bool,
+
+    /// Separator used to join array elements and repeated same-language
+    /// trade names in CSV and SQLite cells
+    #[arg(long, default_value = " | ", value_name = "STR")]
+    pub array_sep: String,
+
+    /// Expand object-valued UDI fields into parent.child columns, N levels
+    /// deep (0: keep the object as one raw-JSON cell)
+    #[arg(long, default_value_t = 1, value_name = "N")]
+    pub flatten_depth: usize,
+
+    /// Split language-tagged array fields (other than tradeNames) into one
+    /// <field>_<LANG> column per language instead of "LANG: text" cells
+    #[arg(long)]
+    pub split_lang_arrays: bool,
+
+    /// Round floats to N decimal places (trailing zeros trimmed)
+    #[arg(long, default_value_t = 10, value_name = "N")]
+    pub float_precision: usize,
+
+    /// Write floats in their shortest exact (round-tripping) form instead
+    /// of rounding them to --float-precision places
+    #[arg(long, conflicts_with = "float_precision")]
+    pub float_raw: bool,
+
+    /// Trade-name layout: one tradeName_<LANG> column per language (wide),
+    /// or tradeName_language + tradeName_text with one row per language
+    /// (long)
+    #[arg(long, value_enum, default_value_t = data::TradeNameLayout::Wide,
+          value_name = "LAYOUT", conflicts_with = "migel")]
+    pub trade_names: data::TradeNameLayout,
+
+    /// SQLite layout: one flat swissdamed table (flat), or devices, udi_dis
+    /// and trade_names tables linked by foreign keys (normalized; also for
+    /// --migel, with the match columns on udi_dis)
+    #[arg(long, value_enum, default_value_t = export::DbSchema::Flat,
+          value_name = "LAYOUT")]
+    pub db_schema: export::DbSchema,
+
+    /// Add an FTS5 full-text table swissdamed_fts(udiDiCode, deviceName,
+    /// tradeNames) to the SQLite output for prefix/substring search, e.g.
+    /// MATCH 'kathet*'
+    #[arg(long)]
+    pub fts: bool,
+
+    /// Write SQLite with the rollback journal and an fsync per commit
+    /// instead of the default bulk load (no journal, one fsync at the end;
+    /// a crash mid-write only loses the new file)
+    #[arg(long)]
+    pub durable: bool,
+
+    /// VACUUM the SQLite output after loading (every DB is ANALYZEd), for
+    /// the smallest deployable file; the size before/after is logged
+    #[arg(long)]
+    pub compact: bool,
+
+    /// Name of the flat SQLite table (index, view, FTS and raw table names
+    /// follow it); letters, digits and _ without quotes or whitespace. A
+    /// full export rebuilds the whole file: add further tables to an
+    /// existing database with --update
+    #[arg(long, default_value = export::DEFAULT_TABLE, value_name = "NAME",
+          value_parser = export::parse_table_name)]
+    pub table: String,
+
+    /// Name of the --migel SQLite table, independent of --table
+    #[arg(long, default_value = export::DEFAULT_TABLE, value_name = "NAME",
+          value_parser = export::parse_table_name)]
+    pub migel_table: String,
+
+    /// Also index these SQLite columns (repeatable or comma-separated), on
+    /// top of the default udiDiCode + tradeName_* indexes; flat layout
+    #[arg(long, value_name = "COL", value_delimiter = ',')]
+    pub index: Vec<String>,
+
+    /// Skip the default udiDiCode + tradeName_* indexes (only the --index
+    /// columns are indexed); flat layout
+    #[arg(long)]
+    pub no_default_indexes: bool,
+
+    /// Create no SQLite indexes at all (faster writes, slower queries)
+    #[arg(long, conflicts_with_all = ["index", "no_default_indexes"])]
+    pub no_index: bool,
+
+    /// Also store each input item's original JSON in a swissdamed_raw
+    /// table (item_id, basicUdi, _raw_json) of the SQLite output, for
+    /// json_extract queries on nested fields the flat columns drop; with
+    /// --migel only the items of matched rows
+    #[arg(long, visible_alias = "raw-json")]
+    pub keep_raw: bool,
+
+    /// Upsert into the existing SQLite database (e.g. -o live.db) instead
+    /// of recreating it, keeping other tables and views: rows are matched
+    /// on (basicUdi, udiDiCode), new columns are added; flat layout only
+    #[arg(long)]
+    pub update: bool,
+
+    /// With --update, also delete rows whose key is no longer in the data
+    #[arg(long, requires = "update")]
+    pub prune: bool,
+
+    /// Declare SQLite columns INTEGER, REAL or BOOLEAN (stored 1/0) when
+    /// every non-empty value fits, with NULL for empty cells; mixed columns
+    /// stay TEXT, CSV and other formats are unchanged
+    #[arg(long)]
+    pub typed: bool,
+
+    /// Declare SQLite TEXT columns COLLATE NOCASE, so = and prefix LIKE
+    /// lookups ignore (ASCII) case and still use the indexes
+    #[arg(long)]
+    pub nocase: bool,
+
+    /// Rows repeating a (basicUdi, udiDiCode) pair in the SQLite table:
+    /// ignore keeps the first, replace keeps the last, fail aborts; the
+    /// number of duplicates is reported
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = export::OnConflict::Ignore)]
+    pub on_conflict: export::OnConflict,
+
+    /// Add a tradeName_preferred column filled from the first non-empty
+    /// language in this order (comma-separated, e.g. DE,FR,IT,EN,ANY)
+    #[arg(long, value_delimiter = ',', value_name = "LANGS")]
+    pub lang_priority: Vec<String>,
+
+    /// Keep only these trade-name languages (comma-separated, e.g.
+    /// DE,FR,IT; ANY for names without a language): other tradeName_<LANG>
+    /// columns and names are dropped
+    #[arg(long, value_delimiter = ',', value_name = "LANGS")]
+    pub langs: Vec<String>,
+
+    /// Replace line breaks inside cell values with TOKEN (default " ⏎ ")
+    /// so every CSV record stays on one line; applies to SQLite and the
+    /// MiGeL output too
+    #[arg(long, value_name = "TOKEN", num_args = 0..=1, default_missing_value = " ⏎ ")]
+    pub escape_newlines: Option<String>,
+
+    /// Replace every run of whitespace inside string values (embedded
+    /// line breaks and tabs included) with a single space
+    #[arg(long)]
+    pub collapse_whitespace: bool,
+
+    /// Fail on input entries of an unexpected shape (non-object item, or a
+    /// udiDis/tradeNames that is not an array) instead of skipping them
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Abort instead of warning when a udiDiCode occurs in more than one
+    /// output row
+    #[arg(long)]
+    pub fail_on_dup_key: bool,
+
+    /// Layout of the --file input (auto: NDJSON for .ndjson/.jsonl files)
+    #[arg(long, value_enum, default_value_t = download::InputFormat::Auto)]
+    pub input_format: download::InputFormat,
+
+    /// Also save the raw downloaded items to this file as
+    /// `{"values": [...]}`, ready to re-feed with --file (skipped with
+    /// --dry-run)
+    #[arg(long, value_name = "PATH", conflicts_with = "file")]
+    pub save_json: Option<PathBuf>,
+
+    /// Only log warnings and errors, and draw no progress bars
+    #[arg(long, short = 'q', conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Log more: -v adds debug output (per-page HTTP status, MiGel
+    /// scoring), -vv trace. RUST_LOG overrides
+    #[arg(long, short = 'v', action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Write the export (or the --migel DB, or the --from-csv/--from-db
+    /// output) here: into a directory with the usual dated name, or to this
+    /// exact file (only one of CSV/SQLite)
+    #[arg(long, short = 'o', value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Write outputs below DIR (created if missing, same csv/, db/, ...
+    /// layout) instead of the app data directory
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
+
+    /// Output file name pattern with {name}, {date} and {ext} placeholders;
+    /// a '/' creates subfolders (e.g. "{date}/{name}.{ext}")
+    #[arg(long, value_name = "TMPL", default_value = export::DEFAULT_NAME_TEMPLATE,
+          value_parser = export::parse_name_template)]
+    pub name_template: String,
 
     /// Page size for API requests (default: 50)
-    #[arg(long, default_value_t = 50)]
+    #[arg(long, default_value_t = download::DEFAULT_PAGE_SIZE)]
     pub page_size: u32,
 
-    /// Deploy SQLite DB to remote server via scp
+    /// Only read the first N UDI items (download stops early, --file
+    /// input is cut off), for quick test runs
+    #[arg(long, value_name = "N",
+          value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub limit: Option<usize>,
+
+    /// UDI API endpoint (default: the public swissdamed.ch basic-udis list)
+    #[arg(long, default_value = download::UDI_BASE_URL)]
+    pub base_url: String,
+
+    /// Load and convert as usual, then print the SQL (CREATE TABLE, VIEW and
+    /// INDEX statements, following --db-schema, --typed, --index, --fts,
+    /// --keep-raw and --table) the SQLite output would get, and stop
+    /// without writing anything
+    #[arg(long, conflicts_with = "migel")]
+    pub print_schema: bool,
+
+    /// Download/load and convert as usual, print the counts, then stop
+    /// before writing or deploying anything (default export, --migel,
+    /// --from-csv and --from-db)
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Also write <name>_<date>.manifest.json (produced files with sha256,
+    /// item/row counts, columns, timings, tool version, CLI arguments) once
+    /// all outputs succeeded; default export, --migel and --diff
+    #[arg(long)]
+    pub manifest: bool,
+
+    /// Only print the number of published UDI items (fetches a single page)
+    #[arg(long)]
+    pub count: bool,
+
+    /// Print machine-readable JSON to stdout instead of plain text
+    /// (--count, --validate, --stats)
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print item, row, distinct udiDiCode and column counts and the
+    /// trade-name languages of the input (file or download), or of the
+    /// given CSV export. No files are written
+    #[arg(long, value_name = "CSV", num_args = 0..=1, default_missing_value = "",
+          value_parser = clap::builder::TypedValueParser::map(
+              clap::builder::OsStringValueParser::new(), PathBuf::from))]
+    pub stats: Option<PathBuf>,
+
+    /// Check the input (file or download) for data-quality problems and
+    /// print a report; exits non-zero on missing basicUdi or duplicate
+    /// udiDiCodes. No files are written
+    #[arg(long)]
+    pub validate: bool,
+
+    /// Deploy SQLite DB to remote server via scp (or --deploy-tool rsync)
     #[arg(long)]
     pub deploy: bool,
 
@@ -123,14 +621,140 @@ pub struct Args {
     #[arg(long)]
     pub scp: Option<String>,
 
+    /// Program used by --deploy; rsync skips unchanged files (--checksum)
+    #[arg(long, value_enum, default_value_t = DeployTool::Scp)]
+    pub deploy_tool: DeployTool,
+
+    /// SSH identity (private key) file for --deploy
+    #[arg(long, value_name = "KEY")]
+    pub ssh_identity: Option<PathBuf>,
+
+    /// SSH port for --deploy
+    #[arg(long, value_name = "N")]
+    pub ssh_port: Option<u16>,
+
+    /// Don't compare the deployed file's SHA-256 with the local copy
+    /// (for targets where remote commands can't run)
+    #[arg(long)]
+    pub skip_verify: bool,
+
     /// Diff two CSV files and output changes to diff/ folder
     #[arg(long, num_args = 2, value_names = ["OLD_CSV", "NEW_CSV"])]
     pub diff: Option<Vec<PathBuf>>,
 
+    /// With --diff: only print added/removed/changed counts and per-column
+    /// change counts, written to diff/summary_<old>_<new>.csv
+    #[arg(long)]
+    pub diff_summary: bool,
+
+    /// With --diff: join column(s) used to match old and new rows
+    /// (repeatable for a composite key; default: udiDiCode)
+    #[arg(long, value_name = "COL")]
+    pub diff_key: Vec<String>,
+
+    /// With --diff: tolerate differing headers (e.g. a new tradeName_XX
+    /// column) by comparing only the shared columns
+    #[arg(long)]
+    pub diff_allow_header_drift: bool,
+
+    /// With --diff: also write the diff as an HTML table to diff/ (added
+    /// rows green, removed red, changed yellow with the changed cells
+    /// highlighted)
+    #[arg(long, requires = "diff", conflicts_with = "diff_summary")]
+    pub diff_html: bool,
+
+    /// Rebuild the SQLite database from an existing CSV export (no download);
+    /// the DB date is taken from a swissdamed_DD.MM.YYYY.csv filename
+    #[arg(long, value_name = "CSV")]
+    pub from_csv: Option<PathBuf>,
+
+    /// Export the swissdamed table of an existing SQLite DB back to CSV (no
+    /// download); the CSV date is taken from a swissdamed_DD.MM.YYYY.db filename
+    #[arg(long, value_name = "DB")]
+    pub from_db: Option<PathBuf>,
+
     /// Match UDI entries against MiGel codes and output matched results
     #[arg(long)]
     pub migel: bool,
 
+    /// With --migel, also write the rows that did not match to this CSV,
+    /// with the reason and the desc_de/desc_fr/desc_it text matched on
+    #[arg(long, value_name = "FILE", requires = "migel")]
+    pub migel_unmatched: Option<PathBuf>,
+
+    /// With --migel, retry products that match nothing with typo-tolerant
+    /// German word matching (edit distance 1-2 on words of 6+ chars); exact
+    /// matches always win. Only items another exact keyword hit already
+    /// found are rescored, so a typo in a product's only matching keyword
+    /// is not recovered
+    #[arg(long, requires = "migel")]
+    pub migel_fuzzy: bool,
+
+    /// With --migel, also match FR/IT keywords against the singular/plural
+    /// of product words of 7+ chars (French -s/-x, Italian final vowel);
+    /// whole words only, never inside a longer word
+    #[arg(long, requires = "migel")]
+    pub migel_romance_fuzzy: bool,
+
+    /// With --migel, add a migel_matched_keywords column listing the MiGeL
+    /// keywords found in each matched product, per language
+    #[arg(long, requires = "migel")]
+    pub migel_explain: bool,
+
+    /// With --migel, weight of MiGeL keywords found in the deviceName
+    /// relative to the trade names (e.g. 1.5 counts them more, 0.5 less)
+    #[arg(long, value_name = "W", default_value_t = 1.0, requires = "migel")]
+    pub weight_device: f64,
+
+    /// With --migel, weight of MiGeL keywords found in the modelName
+    #[arg(long, value_name = "W", default_value_t = 1.0, requires = "migel")]
+    pub weight_model: f64,
+
+    /// With --migel, keep only the highest-scoring matched row per
+    /// udiDiCode (default) or per basicUdi
+    #[arg(
+        long,
+        value_name = "KEY",
+        num_args = 0..=1,
+        default_missing_value = "udiDiCode",
+        requires = "migel"
+    )]
+    pub migel_dedup: Option<reports::MigelDedupKey>,
+
+    /// With --migel, pin products to MiGeL positions from a CSV with
+    /// udiDiCode,migel_code columns (wins over automatic matching)
+    #[arg(long, value_name = "CSV", requires = "migel")]
+    pub migel_overrides: Option<PathBuf>,
+
+    /// With --migel, score the matcher against a gold CSV with
+    /// udiDiCode,expected_migel_code columns (empty or NONE: no match
+    /// expected) and print precision/recall/F1 instead of writing the DB
+    #[arg(long, value_name = "GOLD_CSV", requires = "migel")]
+    pub migel_eval: Option<PathBuf>,
+
+    /// With --migel-eval, write the disagreements (wrong code, false match,
+    /// miss) to this CSV
+    #[arg(long, value_name = "FILE", requires = "migel_eval")]
+    pub migel_eval_out: Option<PathBuf>,
+
+    /// MiGeL XLSX to download for --migel/--migel-validate instead of the
+    /// built-in 01.01.2026 list (e.g. a newer year's spreadsheet)
+    #[arg(long, value_name = "URL")]
+    pub migel_url: Option<String>,
+
+    /// Use a locally downloaded MiGeL XLSX for --migel/--migel-validate and
+    /// skip the download (offline runs)
+    #[arg(long, value_name = "PATH", conflicts_with = "migel_url")]
+    pub migel_file: Option<PathBuf>,
+
+    /// Check the MiGeL XLSX layout: print the sheet names and header rows and
+    /// warn if the columns the parser reads are not where it expects them.
+    /// Uses the given file (or --migel-file), else downloads the list
+    #[arg(long, value_name = "XLSX", num_args = 0..=1, default_missing_value = "",
+          value_parser = clap::builder::TypedValueParser::map(
+              clap::builder::OsStringValueParser::new(), PathBuf::from))]
+    pub migel_validate: Option<PathBuf>,
+
     /// Render the MiGeL stats PNG from the latest existing migel SQLite DB
     #[arg(long)]
     pub migel_stats: bool,
@@ -218,8 +842,216 @@ pub struct Args {
     pub linkedin_delete: Option<String>,
 }
 
+impl Args {
+    /// CSV writer settings selected on the command line.
+    pub fn csv_options(&self) -> export::CsvOptions {
+        export::CsvOptions {
+            bom: !self.no_bom,
+            delimiter: self.delimiter,
+            compress: self.compression(),
+            gzip_level: self.gzip_level(),
+            header_lang: self.header_lang,
+            formula_safe: self.safe_csv,
+            crlf: self.crlf,
+        }
+    }
+
+    /// SQLite writer settings selected on the command line; the `--fts`
+    /// and `--keep-raw` tables are only added by [`Args::write_db`].
+    pub fn sqlite_options(&self) -> export::SqliteOptions<'static> {
+        export::SqliteOptions {
+            durable: self.durable,
+            typed: self.typed,
+            on_conflict: self.on_conflict,
+            indexes: self.index_selection(),
+            compact: self.compact,
+            nocase: self.nocase,
+            ..Default::default()
+        }
+    }
+
+    /// JSON writer settings for `--json-out` and `--ndjson`.
+    pub fn json_options(&self) -> export::JsonOptions {
+        export::JsonOptions {
+            keep_empty: self.keep_empty,
+            pretty: self.pretty,
+            compress: self.compression(),
+            gzip_level: self.gzip_level(),
+        }
+    }
+
+    /// Output compression from `--compress` or `--gzip`.
+    pub fn compression(&self) -> Option<export::Compression> {
+        self.compress
+            .or(self.gzip.then_some(export::Compression::Gzip))
+    }
+
+    /// `--gzip-level`, or the default level.
+    pub fn gzip_level(&self) -> u32 {
+        self.gzip_level.unwrap_or(export::DEFAULT_GZIP_LEVEL)
+    }
+
+    /// True if any output format was selected; otherwise exports default
+    /// to CSV + SQLite.
+    pub fn format_selected(&self) -> bool {
+        self.csv || self.sqlite || self.xlsx || self.json_out || self.ndjson || self.parquet
+    }
+
+    /// Field to deduplicate items on, if `--dedup`/`--dedup-key` is set.
+    pub fn dedup_field(&self) -> Option<&str> {
+        match self.dedup_key {
+            Some(ref key) => Some(key),
+            None if self.dedup => Some("basicUdi"),
+            None => None,
+        }
+    }
+
+    /// Item filters selected on the command line.
+    pub fn filter_options(&self) -> data::FilterOptions<'_> {
+        data::FilterOptions {
+            statuses: &self.status,
+            companies: &self.company,
+            risk_classes: &self.risk_class,
+            include_unknown_risk_class: self.include_unknown_risk_class,
+        }
+    }
+
+    /// Provenance for the SQLite `_meta` table: the input file(s), or the
+    /// API endpoint and page size when downloading.
+    pub fn harvest_meta(&self) -> export::HarvestMeta {
+        if self.file.is_empty() {
+            export::HarvestMeta {
+                source_url: Some(self.base_url.clone()),
+                page_size: Some(self.page_size),
+                invocation: Some(manifest::command_line()),
+                ..Default::default()
+            }
+        } else {
+            let files: Vec<String> = self.file.iter().map(|f| f.display().to_string()).collect();
+            export::HarvestMeta {
+                source_url: Some(files.join(", ")),
+                invocation: Some(manifest::command_line()),
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Write the UDI/MiGeL database in the `--db-schema` layout, plus the
+    /// `--fts` table and the `--keep-raw` table of `raw_items`; `table` is
+    /// the flat table (`--table`/`--migel-table`), `meta` usually extends
+    /// [`Args::harvest_meta`].
+    pub fn write_db(
+        &self,
+        headers: &[String],
+        rows: &[Vec<String>],
+        filename: &str,
+        table: &str,
+        meta: &export::HarvestMeta,
+        raw_items: Option<&export::RawItemSpool>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let opts = export::SqliteOptions {
+            fts: self.fts,
+            raw_items,
+            ..self.sqlite_options()
+        };
+        match self.db_schema {
+            export::DbSchema::Flat if self.update => {
+                export::update_sqlite(headers, rows, filename, table, meta, self.prune, &opts)?;
+            }
+            export::DbSchema::Flat => {
+                export::write_sqlite_keyed(headers, rows, filename, table, meta, &opts)?
+            }
+            export::DbSchema::Normalized => {
+                export::write_sqlite_normalized(headers, rows, filename, meta, &opts)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject the flat-layout-only options with `--db-schema normalized`.
+    /// Checked here instead of clap's `conflicts_with`, which would also
+    /// reject an explicit `--db-schema flat`.
+    pub fn check_db_schema(&self) -> Result<(), String> {
+        if self.db_schema == export::DbSchema::Flat {
+            return Ok(());
+        }
+        let given = [
+            (
+                "--trade-names long",
+                self.trade_names == data::TradeNameLayout::Long,
+            ),
+            ("--table", self.table != export::DEFAULT_TABLE),
+            ("--migel-table", self.migel_table != export::DEFAULT_TABLE),
+            ("--index", !self.index.is_empty()),
+            ("--no-default-indexes", self.no_default_indexes),
+            ("--update", self.update),
+        ];
+        match given.iter().find(|(_, set)| *set) {
+            Some((option, _)) => Err(format!("{} needs --db-schema flat", option)),
+            None => Ok(()),
+        }
+    }
+
+    /// Flat table names chosen by `--table`/`--migel-table`.
+    pub fn table_names(&self) -> export::TableNames<'_> {
+        export::TableNames {
+            udi: &self.table,
+            migel: &self.migel_table,
+        }
+    }
+
+    /// SQLite indexes chosen by `--index`/`--no-index`.
+    pub fn index_selection(&self) -> export::IndexSelection {
+        export::IndexSelection {
+            defaults: !self.no_index && !self.no_default_indexes,
+            columns: self.index.clone(),
+        }
+    }
+
+    /// Cell formatting selected on the command line.
+    pub fn value_format(&self) -> data::ValueFormat<'_> {
+        data::ValueFormat {
+            array_sep: &self.array_sep,
+            float_precision: (!self.float_raw).then_some(self.float_precision),
+            lang_priority: &self.lang_priority,
+            newline_token: self.escape_newlines.as_deref(),
+            flatten_depth: self.flatten_depth,
+            split_lang_arrays: self.split_lang_arrays,
+            langs: &self.langs,
+            collapse_whitespace: self.collapse_whitespace,
+        }
+    }
+}
+
 // --- Main ---
 
+/// Route `log` output to stderr at the level chosen by `-q`/`-v` (or
+/// `RUST_LOG`). Info lines are printed bare, like the plain messages they
+/// replaced; other levels get a prefix.
+fn init_logging(args: &Args) {
+    use std::io::Write;
+
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => log::LevelFilter::Warn,
+        (false, 0) => log::LevelFilter::Info,
+        (false, 1) => log::LevelFilter::Debug,
+        (false, _) => log::LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        // Dependencies (reqwest, hyper, ...) only log warnings unless
+        // RUST_LOG asks for more.
+        .filter_level(log::LevelFilter::Warn.min(level))
+        .filter_module(env!("CARGO_CRATE_NAME"), level)
+        .parse_env("RUST_LOG")
+        .format(|buf, record| match record.level() {
+            log::Level::Info => writeln!(buf, "{}", record.args()),
+            log::Level::Warn => writeln!(buf, "Warning: {}", record.args()),
+            log::Level::Error => writeln!(buf, "Error: {}", record.args()),
+            level => writeln!(buf, "[{} {}] {}", level, record.target(), record.args()),
+        })
+        .init();
+}
+
 /// Show an error dialog using a minimal eframe window (GUI mode)
 /// or just print to stderr (CLI mode).
 fn show_error_dialog(message: &str, is_gui_mode: bool) {
@@ -230,7 +1062,7 @@ fn show_error_dialog(message: &str, is_gui_mode: bool) {
 }
 
 fn main() {
-    let is_gui_mode = std::env::args().skip(1).next().is_none();
+    let is_gui_mode = std::env::args().nth(1).is_none();
     if let Err(e) = run() {
         show_error_dialog(&e.to_string(), is_gui_mode);
         std::process::exit(1);
@@ -258,15 +1090,150 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let args = Args::parse();
+    if args.gzip_level.is_some() && args.compression() != Some(export::Compression::Gzip) {
+        return Err(
+            "--gzip-level only applies to gzip compression (--gzip or --compress gzip)".into(),
+        );
+    }
+    args.check_db_schema()?;
+    init_logging(&args);
+    progress::set_quiet(args.quiet);
+    export::set_date_format(args.date_format);
+    export::set_output_layout(args.out_dir.clone(), &args.name_template);
+    manifest::start();
 
     // Handle --diff mode
     if let Some(ref diff_files) = args.diff {
-        return diff::diff_csv_files(&diff_files[0], &diff_files[1]);
+        let opts = diff::DiffOptions {
+            summary: args.diff_summary,
+            keys: args.diff_key.clone(),
+            allow_header_drift: args.diff_allow_header_drift,
+            html: args.diff_html,
+            csv: args.csv_options(),
+        };
+        diff::diff_csv_files(&diff_files[0], &diff_files[1], &opts)?;
+        return write_manifest(&args, "swissdamed_diff");
+    }
+
+    // Handle --from-csv mode (CSV export -> SQLite, same schema and indexes)
+    if let Some(ref csv_path) = args.from_csv {
+        let (headers, rows) = diff::read_csv_rows(csv_path, &args.csv_options())?;
+        let headers = export::technical_headers(&headers, export::detect_header_lang(&headers));
+        info!(
+            "Read {} rows with {} columns from {}",
+            rows.len(),
+            headers.len(),
+            csv_path.display()
+        );
+        if args.dry_run {
+            info!("Dry run: no files written.");
+            return Ok(());
+        }
+        let filename = match diff::extract_date_from_filename(csv_path) {
+            Some(date) => export::output_db_dated("swissdamed", &date)?,
+            None => export::output_db("swissdamed")?,
+        };
+        let filename = export::with_output(filename, args.output.as_deref())?;
+        let meta = export::HarvestMeta {
+            source_url: Some(csv_path.display().to_string()),
+            invocation: Some(manifest::command_line()),
+            ..Default::default()
+        };
+        export::write_sqlite_keyed(
+            &headers,
+            &rows,
+            &filename,
+            &args.table,
+            &meta,
+            &export::SqliteOptions {
+                fts: args.fts,
+                ..args.sqlite_options()
+            },
+        )?;
+        info!("SQLite written: {}", filename);
+        export::compress_db(&filename, args.compression(), args.gzip_level())?;
+        return Ok(());
+    }
+
+    // Handle --from-db mode (SQLite -> CSV in the table's column order)
+    if let Some(ref db_path) = args.from_db {
+        let (headers, rows) = export::read_sqlite_table(&db_path.to_string_lossy(), &args.table)?;
+        info!(
+            "Read {} rows with {} columns from {}",
+            rows.len(),
+            headers.len(),
+            db_path.display()
+        );
+        if args.dry_run {
+            info!("Dry run: no files written.");
+            return Ok(());
+        }
+        let filename = match diff::extract_date_from_filename(db_path) {
+            Some(date) => export::output_csv_dated("swissdamed", &date)?,
+            None => export::output_csv("swissdamed")?,
+        };
+        let filename = export::with_output(filename, args.output.as_deref())?;
+        let filename = export::write_csv_with(&headers, &rows, &filename, &args.csv_options())?;
+        info!("CSV written: {}", filename);
+        return Ok(());
+    }
+
+    // Handle --count mode (dataset size only, no files written)
+    if args.count {
+        let total = download::fetch_total_count(&args.base_url)?;
+        if args.json {
+            println!("{}", serde_json::json!({ "count": total }));
+        } else {
+            println!("{}", total);
+        }
+        return Ok(());
+    }
+
+    // Handle --stats mode (counts only, no files written)
+    if let Some(ref csv_path) = args.stats {
+        return reports::run_stats(&args, csv_path);
+    }
+
+    // Handle --validate mode (report only, no files written)
+    if args.validate {
+        let mut report = validate::ValidationReport::default();
+        reports::for_each_input_item(&args, &mut |item| {
+            report.push(&item);
+            Ok(())
+        })?;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            report.print_table();
+        }
+        if report.has_hard_problems() {
+            return Err("Validation failed: missing basicUdi or duplicate udiDiCodes".into());
+        }
+        return Ok(());
+    }
+
+    // Handle --migel-validate mode (layout check only, no files written)
+    if let Some(ref path) = args.migel_validate {
+        let path = if path.as_os_str().is_empty() {
+            reports::migel_xlsx_path(&args)?
+        } else {
+            path.to_string_lossy().into_owned()
+        };
+        let problems = migel::validate_migel_layout(&path)?;
+        if !problems.is_empty() {
+            return Err(format!(
+                "MiGeL XLSX layout check failed ({} problems)",
+                problems.len()
+            )
+            .into());
+        }
+        return Ok(());
     }
 
     // Handle --migel mode
     if args.migel {
-        return reports::run_migel(&args);
+        reports::run_migel(&args)?;
+        return write_manifest(&args, "swissdamed_migel");
     }
 
     // Handle --sigvaris-shop mode (scrape shop.sigvaris.com, build override DB)
@@ -282,21 +1249,22 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // Handle --migel-stats mode (render PNG from existing DBs, no download)
     if args.migel_stats {
-        let db_dir = app_data_dir().join("db");
+        let db_dir = export::output_root().join("db");
         let (migel_db, full_db) = migel_stats::find_latest_dbs(&db_dir);
         let migel_db = migel_db
             .ok_or_else(|| format!("No swissdamed_migel_*.db found in {}", db_dir.display()))?;
-        eprintln!("Reading from {}", migel_db.display());
-        let png_dir = app_data_dir().join("png");
-        let png_path = migel_stats::generate(&migel_db, full_db.as_deref(), &png_dir)?;
+        info!("Reading from {}", migel_db.display());
+        let png_dir = export::output_root().join("png");
+        let png_path =
+            migel_stats::generate(&migel_db, full_db.as_deref(), &png_dir, args.table_names())?;
         if args.linkedin {
-            if let Err(e) = linkedin::publish_image(&png_path, &migel_db) {
-                eprintln!("LinkedIn publish failed: {}", e);
+            if let Err(e) = linkedin::publish_image(&png_path, &migel_db, &args.migel_table) {
+                warn!("LinkedIn publish failed: {}", e);
             }
         }
         if args.twitter {
-            if let Err(e) = twitter::publish_image(&png_path, &migel_db) {
-                eprintln!("Twitter publish failed: {}", e);
+            if let Err(e) = twitter::publish_image(&png_path, &migel_db, &args.migel_table) {
+                warn!("Twitter publish failed: {}", e);
             }
         }
         return Ok(());
@@ -334,7 +1302,7 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // Handle --actors and --mandates
     if args.actors || args.mandates {
-        let (do_csv, do_sqlite) = if !args.csv && !args.sqlite {
+        let (do_csv, do_sqlite) = if !args.format_selected() {
             (true, true)
         } else {
             (args.csv, args.sqlite)
@@ -366,7 +1334,7 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Default: download UDI products
-    let (do_csv, do_sqlite) = if !args.csv && !args.sqlite {
+    let (do_csv, do_sqlite) = if !args.format_selected() {
         (true, true)
     } else if args.deploy && !args.sqlite {
         (args.csv, true)
@@ -374,32 +1342,145 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
         (args.csv, args.sqlite)
     };
 
-    let values = if let Some(ref path) = args.file {
-        eprintln!("Loading from file: {}", path.display());
-        download::load_json_file(path)?
-    } else {
-        download::download_all_pages(args.page_size)?
+    if let Some(ref output) = args.output {
+        let formats = [
+            do_csv,
+            do_sqlite,
+            args.xlsx,
+            args.json_out,
+            args.ndjson,
+            args.parquet,
+        ]
+        .iter()
+        .filter(|&&f| f)
+        .count();
+        if formats > 1 && !export::is_output_dir(output) {
+            return Err(format!(
+                "--output {} is a file, but several formats would be written; \
+                 pass a directory or select a single format",
+                output.display()
+            )
+            .into());
+        }
+    }
+
+    let mut builder = data::RowBuilder::new(args.value_format());
+    let raw_items = match args.keep_raw && do_sqlite {
+        true => Some(export::RawItemSpool::new()?),
+        false => None,
     };
+    reports::for_each_input_item(&args, &mut |item| {
+        builder.push(&item);
+        if let Some(ref raw_items) = raw_items {
+            raw_items.push(&data::get_field(&item, "basicUdi"), &item.to_string())?;
+        }
+        Ok(())
+    })?;
 
-    if values.is_empty() {
-        eprintln!("No data found.");
+    let item_count = builder.item_count();
+    if item_count == 0 {
+        info!("No data found.");
         return Ok(());
     }
 
-    let (headers, trade_name_langs) = data::collect_headers(&values);
-    let rows = data::build_rows(&values, &headers, &trade_name_langs);
+    // Compared before any output so --fail-on-schema-change writes nothing;
+    // the new list is stored only once every output is written
+    let snapshot = match args.schema_snapshot {
+        Some(ref path) => {
+            let discovered = builder.headers();
+            data::check_schema_snapshot(path, &discovered, args.fail_on_schema_change)?;
+            Some((path, discovered))
+        }
+        None => None,
+    };
+
+    let headers = match args.schema {
+        Some(ref path) => {
+            let schema = data::read_schema(path)?;
+            data::apply_schema(builder.headers(), &schema, args.schema_strict)
+        }
+        None => builder.headers(),
+    };
+    let headers = data::select_columns(headers, &args.columns, &args.exclude_columns)?;
+    if let Some(ref path) = args.write_schema {
+        data::write_schema(&headers, path)?;
+        info!(
+            "Schema written: {} ({} columns)",
+            path.display(),
+            headers.len()
+        );
+    }
+    let rows = builder.into_rows(&headers);
+    let (headers, mut rows) = match args.trade_names {
+        data::TradeNameLayout::Wide => (headers, rows),
+        data::TradeNameLayout::Long => data::trade_names_long(&headers, rows),
+    };
+    if !args.sort_by.is_empty() {
+        data::sort_rows(&headers, &mut rows, &args.sort_by)?;
+    }
 
-    eprintln!(
+    info!(
         "Processed {} items, generated {} rows with {} columns.",
-        values.len(),
+        item_count,
         rows.len(),
         headers.len()
     );
+    manifest::record_table(Some(item_count), &headers, rows.len());
 
-    if do_csv {
-        let filename = export::output_csv("swissdamed")?;
-        export::write_csv(&headers, &rows, &filename)?;
-        eprintln!("CSV written: {}", filename);
+    // The long layout repeats each udiDiCode once per language.
+    let dup_key: &[&str] = match args.trade_names {
+        data::TradeNameLayout::Wide => &["udiDiCode"],
+        data::TradeNameLayout::Long => &["udiDiCode", data::TRADE_NAME_LANGUAGE],
+    };
+    data::check_duplicate_row_keys(&headers, &rows, dup_key, args.fail_on_dup_key)?;
+
+    if args.print_schema {
+        print!(
+            "{}",
+            export::schema_sql(
+                &headers,
+                &rows,
+                &args.table,
+                args.db_schema,
+                args.fts,
+                args.keep_raw,
+                &args.sqlite_options(),
+            )?
+        );
+        return Ok(());
+    }
+
+    if args.dry_run {
+        info!("Dry run: no files written.");
+        return Ok(());
+    }
+
+    if let (true, Some(column)) = (do_csv, &args.split_by) {
+        let dir = match args.output.as_deref() {
+            Some(out) if export::is_output_dir(out) => {
+                out.join(format!("by_{}", export::safe_file_name(column)))
+            }
+            _ => export::output_split_dir(column)?,
+        };
+        let written = export::write_csv_split(&headers, &rows, column, &dir, &args.csv_options())?;
+        info!(
+            "Split by {}: {} files in {}",
+            column,
+            written.len(),
+            dir.display()
+        );
+        for (filename, count) in &written {
+            info!("  {} ({} rows)", filename, count);
+            manifest::record_file(filename);
+        }
+    }
+
+    if do_csv && !args.no_combined {
+        let filename =
+            export::with_output(export::output_csv("swissdamed")?, args.output.as_deref())?;
+        let filename = export::write_csv_with(&headers, &rows, &filename, &args.csv_options())?;
+        info!("CSV written: {}", filename);
+        manifest::record_file(&filename);
         if args.gdrive {
             gdrive::gdrive_upload_csv(&args, &filename)?;
         }
@@ -408,28 +1489,90 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    if args.xlsx {
+        let filename =
+            export::with_output(export::output_xlsx("swissdamed")?, args.output.as_deref())?;
+        export::write_xlsx(
+            &export::localize_headers(&headers, args.header_lang),
+            &rows,
+            &filename,
+        )?;
+        info!("XLSX written: {}", filename);
+        manifest::record_file(&filename);
+    }
+
+    if args.json_out {
+        let filename =
+            export::with_output(export::output_json("swissdamed")?, args.output.as_deref())?;
+        let filename = export::write_json(&headers, &rows, &filename, args.json_options())?;
+        info!("JSON written: {}", filename);
+        manifest::record_file(&filename);
+    }
+
+    if args.ndjson {
+        let filename =
+            export::with_output(export::output_ndjson("swissdamed")?, args.output.as_deref())?;
+        let filename = export::write_ndjson(&headers, &rows, &filename, args.json_options())?;
+        info!("NDJSON written: {}", filename);
+        manifest::record_file(&filename);
+    }
+
+    if args.parquet {
+        let filename = export::with_output(
+            export::output_parquet("swissdamed")?,
+            args.output.as_deref(),
+        )?;
+        export::write_parquet(&headers, &rows, &filename)?;
+        info!("Parquet written: {}", filename);
+        manifest::record_file(&filename);
+    }
+
     if do_sqlite {
-        let filename = export::output_db("swissdamed")?;
-        export::write_sqlite(&headers, &rows, &filename)?;
-        eprintln!("SQLite written: {}", filename);
+        let filename =
+            export::with_output(export::output_db("swissdamed")?, args.output.as_deref())?;
+        let meta = export::HarvestMeta {
+            item_count: Some(item_count),
+            ..args.harvest_meta()
+        };
+        args.write_db(
+            &headers,
+            &rows,
+            &filename,
+            &args.table,
+            &meta,
+            raw_items.as_ref(),
+        )?;
+        info!("SQLite written: {}", filename);
+        manifest::record_file(&filename);
+        // With --compress, deploy the smaller sidecar instead of the live DB
+        let filename = export::compress_db(&filename, args.compression(), args.gzip_level())?
+            .unwrap_or(filename);
+        manifest::record_file(&filename);
 
         if args.deploy {
             let config = Config::load();
             let scp_target = resolve_setting(&args.scp, &config.scp, "scp")?;
-            eprintln!("Deploying {} to {} ...", filename, scp_target);
-            let status = Command::new("scp")
-                .arg(&filename)
-                .arg(&scp_target)
-                .status()?;
-
-            if status.success() {
-                eprintln!("Deploy successful.");
-            } else {
-                eprintln!("Deploy failed with exit code: {}", status);
-                return Err("scp failed".into());
-            }
+            deploy_file(&args, &filename, &scp_target)?;
         }
     }
 
+    if let Some((path, discovered)) = snapshot {
+        data::write_schema(&discovered, path)?;
+        info!(
+            "Schema snapshot written: {} ({} columns)",
+            path.display(),
+            discovered.len()
+        );
+    }
+
+    write_manifest(&args, "swissdamed")
+}
+
+/// Write the `--manifest` file for a run whose outputs all succeeded.
+fn write_manifest(args: &Args, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if args.manifest {
+        let path = manifest::write(name, args.output.as_deref())?;
+        info!("Manifest written: {}", path);
+    }
     Ok(())
 }
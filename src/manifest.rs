@@ -0,0 +1,220 @@
+//! Machine-readable run manifest for `--manifest`: the files a run produced
+//! (with size and sha256), item/row counts, the column list, timings, the
+//! tool version and the CLI arguments.
+//!
+//! The writers report what they produce through [`record_file`] and
+//! friends; `run` writes the manifest once every writer has succeeded.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::warn;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Facts collected while the run writes its outputs.
+struct Run {
+    started_at: Option<DateTime<Utc>>,
+    download: Option<Duration>,
+    item_count: Option<usize>,
+    row_count: Option<usize>,
+    columns: Vec<String>,
+    files: Vec<String>,
+}
+
+static RUN: Mutex<Run> = Mutex::new(Run {
+    started_at: None,
+    download: None,
+    item_count: None,
+    row_count: None,
+    columns: Vec::new(),
+    files: Vec::new(),
+});
+
+fn run() -> MutexGuard<'static, Run> {
+    RUN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Run {
+    fn add_file(&mut self, path: &str) {
+        if !self.files.iter().any(|f| f == path) {
+            self.files.push(path.to_string());
+        }
+    }
+
+    /// The manifest document; files that vanished since are skipped with
+    /// a warning.
+    fn to_json(
+        &self,
+        arguments: Vec<String>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let timestamp = |t: DateTime<Utc>| t.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let mut files = Vec::with_capacity(self.files.len());
+        for path in &self.files {
+            let bytes = match fs::metadata(path) {
+                Ok(meta) => meta.len(),
+                Err(e) => {
+                    warn!("Manifest: skipping {}: {}", path, e);
+                    continue;
+                }
+            };
+            files.push(serde_json::json!({
+                "path": path,
+                "bytes": bytes,
+                "sha256": sha256_file(path)?,
+            }));
+        }
+
+        Ok(serde_json::json!({
+            "tool_version": env!("CARGO_PKG_VERSION"),
+            "arguments": arguments,
+            "started_at": self.started_at.map(timestamp),
+            "finished_at": timestamp(Utc::now()),
+            "download_seconds": self.download.map(|d| d.as_secs_f64()),
+            "item_count": self.item_count,
+            "row_count": self.row_count,
+            "columns": self.columns,
+            "files": files,
+        }))
+    }
+}
+
+/// Mark the start of the run; called once at startup.
+pub fn start() {
+    run().started_at = Some(Utc::now());
+}
+
+/// Time spent downloading the input from the API.
+pub fn record_download(elapsed: Duration) {
+    let mut run = run();
+    run.download = Some(run.download.unwrap_or_default() + elapsed);
+}
+
+/// The table the run wrote: input items (when known), rows and columns.
+pub fn record_table(item_count: Option<usize>, headers: &[String], row_count: usize) {
+    let mut run = run();
+    run.item_count = item_count;
+    run.row_count = Some(row_count);
+    run.columns = headers.to_vec();
+}
+
+/// A file the run produced; recording the same path twice lists it once.
+pub fn record_file(path: &str) {
+    run().add_file(path);
+}
+
+/// The command line as one shell-style string, see [`shell_join`].
+pub fn command_line() -> String {
+    shell_join(std::env::args())
+}
+
+/// Join arguments shell-style; empty arguments and those with whitespace,
+/// quotes, `\`, `$` or `` ` `` are single-quoted.
+fn shell_join(args: impl Iterator<Item = String>) -> String {
+    args.map(|arg| {
+        if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "'\"\\$`".contains(c)) {
+            arg
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Hex-encoded sha256 of a file, streamed.
+fn sha256_file(path: &str) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Write the manifest to [`crate::export::output_manifest`] and return its
+/// path.
+pub fn write(name: &str, output: Option<&Path>) -> Result<String, Box<dyn std::error::Error>> {
+    let manifest = run().to_json(std::env::args().skip(1).collect())?;
+    let path = crate::export::output_manifest(name, output)?;
+    fs::write(&path, serde_json::to_string_pretty(&manifest)? + "\n")?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plain arguments stay as they are, the rest is single-quoted with
+    /// embedded single quotes escaped.
+    #[test]
+    fn shell_join_quotes_special_arguments() {
+        let args = [
+            "swissdamed2sqlite",
+            "--file",
+            "my items.json",
+            "",
+            "it's",
+            "$HOME",
+            "a\\b",
+        ];
+        assert_eq!(
+            shell_join(args.iter().map(|a| a.to_string())),
+            r#"swissdamed2sqlite --file 'my items.json' '' 'it'\''s' '$HOME' 'a\b'"#
+        );
+    }
+
+    /// Each recorded file is listed once with its size and sha256; files
+    /// gone by the time the manifest is written are left out.
+    #[test]
+    fn manifest_lists_files_with_checksums() {
+        let dir = std::env::temp_dir();
+        let path = |name: &str| {
+            dir.join(format!(
+                "swissdamed2sqlite_manifest_test_{}_{}",
+                std::process::id(),
+                name
+            ))
+            .to_string_lossy()
+            .to_string()
+        };
+        let (csv, missing) = (path("out.csv"), path("missing.db"));
+        fs::write(&csv, "abc").unwrap();
+        let mut run = Run {
+            started_at: None,
+            download: Some(Duration::from_millis(1500)),
+            item_count: Some(3),
+            row_count: Some(4),
+            columns: vec!["basicUdi".to_string(), "udiDiCode".to_string()],
+            files: Vec::new(),
+        };
+        run.add_file(&csv);
+        run.add_file(&missing);
+        run.add_file(&csv);
+        assert_eq!(run.files, [csv.clone(), missing]);
+
+        let manifest = run.to_json(vec!["--manifest".to_string()]).unwrap();
+        fs::remove_file(&csv).ok();
+        assert_eq!(
+            manifest["files"],
+            serde_json::json!([{
+                "path": csv,
+                "bytes": 3,
+                "sha256": "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            }])
+        );
+        assert_eq!(manifest["arguments"], serde_json::json!(["--manifest"]));
+        assert_eq!(manifest["download_seconds"], 1.5);
+        assert_eq!(manifest["item_count"], 3);
+        assert_eq!(manifest["row_count"], 4);
+        assert_eq!(
+            manifest["columns"],
+            serde_json::json!(["basicUdi", "udiDiCode"])
+        );
+        assert!(manifest["started_at"].is_null());
+        assert_eq!(manifest["tool_version"], env!("CARGO_PKG_VERSION"));
+    }
+}
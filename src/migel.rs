@@ -1,5 +1,6 @@
 use aho_corasick::{AhoCorasick, Input, StartKind};
 use calamine::{open_workbook, Reader, Xlsx};
+use log::{debug, info, log_enabled, warn, Level};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use unicode_normalization::UnicodeNormalization;
@@ -22,6 +23,9 @@ pub struct MigelItem {
     pub secondary_it: Vec<String>,
     /// DE category hierarchy keywords (from parent categories in XLSX)
     pub category_de: Vec<String>,
+    /// DE category path (product group A, then levels B–G) above this
+    /// position, outermost first
+    pub category: Vec<String>,
     /// Union of all keywords (used for candidate index)
     pub all_keywords: Vec<String>,
     /// Parsed physical dimensions of this position (for size-aware routing).
@@ -1598,6 +1602,226 @@ fn cell_str(row: &[calamine::Data], idx: usize) -> String {
         .to_string()
 }
 
+/// Sheet order of the MiGeL XLSX: German, French, Italian.
+pub const MIGEL_SHEET_LANGS: [&str; 3] = ["DE", "FR", "IT"];
+
+/// A column of the MiGeL XLSX that the parser reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigelColumn {
+    PositionNr,
+    Bezeichnung,
+    Limitation,
+}
+
+impl MigelColumn {
+    pub const ALL: [MigelColumn; 3] = [
+        MigelColumn::PositionNr,
+        MigelColumn::Bezeichnung,
+        MigelColumn::Limitation,
+    ];
+
+    /// Column index used when the header is not recognized (H, J, K).
+    pub fn default_index(self) -> usize {
+        match self {
+            MigelColumn::PositionNr => 7,
+            MigelColumn::Bezeichnung => 9,
+            MigelColumn::Limitation => 10,
+        }
+    }
+
+    /// Expected header text per sheet language (`MIGEL_SHEET_LANGS` order).
+    pub fn headers(self) -> [&'static str; 3] {
+        match self {
+            MigelColumn::PositionNr => ["Positions-Nr.", "No pos.", "No pos."],
+            MigelColumn::Bezeichnung => ["Bezeichnung", "Dénomination", "Denominazione"],
+            MigelColumn::Limitation => ["Limitation", "Limitation", "Limitazione"],
+        }
+    }
+
+    /// Whether `cell` is this column's header on the sheet of language
+    /// `lang` (index into `MIGEL_SHEET_LANGS`). Compared on letters and
+    /// digits only, case- and accent-insensitively, so "Positions-Nr" or
+    /// "N° pos." still match.
+    pub fn matches_header(self, lang: usize, cell: &str) -> bool {
+        let key = header_key(cell);
+        let expected = header_key(self.headers()[lang]);
+        key == expected || (self == MigelColumn::PositionNr && lang > 0 && key == "npos")
+    }
+}
+
+fn header_key(s: &str) -> String {
+    s.nfkd()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Column indices the parser reads on one sheet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MigelColumns {
+    pub position_nr: usize,
+    pub bezeichnung: usize,
+    pub limitation: usize,
+}
+
+impl Default for MigelColumns {
+    fn default() -> Self {
+        MigelColumns {
+            position_nr: MigelColumn::PositionNr.default_index(),
+            bezeichnung: MigelColumn::Bezeichnung.default_index(),
+            limitation: MigelColumn::Limitation.default_index(),
+        }
+    }
+}
+
+impl MigelColumns {
+    pub fn get(&self, col: MigelColumn) -> usize {
+        match col {
+            MigelColumn::PositionNr => self.position_nr,
+            MigelColumn::Bezeichnung => self.bezeichnung,
+            MigelColumn::Limitation => self.limitation,
+        }
+    }
+
+    fn set(&mut self, col: MigelColumn, idx: usize) {
+        match col {
+            MigelColumn::PositionNr => self.position_nr = idx,
+            MigelColumn::Bezeichnung => self.bezeichnung = idx,
+            MigelColumn::Limitation => self.limitation = idx,
+        }
+    }
+
+    /// Locate the columns in a sheet's header row by their header text.
+    /// Columns whose header is not recognized keep their default index and
+    /// are returned in the second element.
+    pub fn detect(header: &[String], lang: usize) -> (Self, Vec<MigelColumn>) {
+        let mut cols = MigelColumns::default();
+        let mut missing = Vec::new();
+        for col in MigelColumn::ALL {
+            match header.iter().position(|h| col.matches_header(lang, h)) {
+                Some(idx) => cols.set(col, idx),
+                None => missing.push(col),
+            }
+        }
+        (cols, missing)
+    }
+}
+
+fn header_row(range: &calamine::Range<calamine::Data>) -> Vec<String> {
+    range
+        .rows()
+        .next()
+        .map(|row| (0..row.len()).map(|i| cell_str(row, i)).collect())
+        .unwrap_or_default()
+}
+
+/// Detect the parser columns of one sheet, warning for every column that
+/// falls back to its default index.
+fn sheet_columns(
+    range: &calamine::Range<calamine::Data>,
+    lang: usize,
+    sheet_name: &str,
+) -> MigelColumns {
+    let (cols, missing) = MigelColumns::detect(&header_row(range), lang);
+    for col in missing {
+        warn!(
+            "MiGeL sheet '{}': no \"{}\" header found, assuming column {}",
+            sheet_name,
+            col.headers()[lang],
+            column_letter(col.default_index())
+        );
+    }
+    if cols != MigelColumns::default() {
+        info!(
+            "MiGeL sheet '{}': columns moved, using {}/{}/{}",
+            sheet_name,
+            column_letter(cols.position_nr),
+            column_letter(cols.bezeichnung),
+            column_letter(cols.limitation)
+        );
+    }
+    cols
+}
+
+/// Column letter for a 0-based index (0 → A, 26 → AA).
+fn column_letter(mut idx: usize) -> String {
+    let mut out = String::new();
+    loop {
+        out.insert(0, (b'A' + (idx % 26) as u8) as char);
+        if idx < 26 {
+            return out;
+        }
+        idx = idx / 26 - 1;
+    }
+}
+
+/// `--migel-validate`: print the sheet names and header rows of the MiGeL
+/// XLSX and check that the parser can locate its columns by header text on
+/// the DE/FR/IT sheets. Returns the problems found; an empty list means the
+/// layout is usable.
+pub fn validate_migel_layout(path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    let sheet_names: Vec<String> = workbook.sheet_names().to_vec();
+    let mut problems = Vec::new();
+
+    println!("Sheets: {}", sheet_names.join(", "));
+    if sheet_names.len() < MIGEL_SHEET_LANGS.len() {
+        problems.push(format!(
+            "expected {} sheets (DE, FR, IT), found {}",
+            MIGEL_SHEET_LANGS.len(),
+            sheet_names.len()
+        ));
+    }
+
+    for (lang, sheet_name) in sheet_names.iter().enumerate().take(MIGEL_SHEET_LANGS.len()) {
+        let range = workbook.worksheet_range(sheet_name)?;
+        let header = header_row(&range);
+
+        println!();
+        println!("Sheet {} ({}):", lang, sheet_name);
+        for (i, h) in header.iter().enumerate() {
+            println!(
+                "  {:>2}  {}",
+                column_letter(i),
+                h.replace(['\r', '\n'], " ")
+            );
+        }
+
+        let (cols, missing) = MigelColumns::detect(&header, lang);
+        for col in MigelColumn::ALL {
+            if missing.contains(&col) {
+                let idx = col.default_index();
+                problems.push(format!(
+                    "sheet {} ({}): no \"{}\" header ({}); the parser would fall back to column {} (\"{}\")",
+                    lang,
+                    sheet_name,
+                    col.headers()[lang],
+                    MIGEL_SHEET_LANGS[lang],
+                    column_letter(idx),
+                    header.get(idx).map(String::as_str).unwrap_or("")
+                ));
+            } else if cols.get(col) != col.default_index() {
+                println!(
+                    "  \"{}\" detected in column {} (default {})",
+                    col.headers()[lang],
+                    column_letter(cols.get(col)),
+                    column_letter(col.default_index())
+                );
+            }
+        }
+    }
+
+    println!();
+    if problems.is_empty() {
+        println!("Layout OK: all parser columns found on the DE/FR/IT sheets.");
+    } else {
+        for p in &problems {
+            warn!("{}", p);
+        }
+    }
+    Ok(problems)
+}
+
 /// Parse all MiGeL items (rows with a Positions-Nr.) from the XLSX file.
 /// Keeps per-language keywords separate for scoring, and builds a combined
 /// keyword set for candidate finding.
@@ -1607,9 +1831,12 @@ pub fn parse_migel_items(path: &str) -> Result<Vec<MigelItem>, Box<dyn Error>> {
 
     // --- Pass 1: Parse German sheet (index 0) ---
     let range_de = workbook.worksheet_range(&sheet_names[0])?;
+    let cols_de = sheet_columns(&range_de, 0, &sheet_names[0]);
 
     // Track category hierarchy descriptions (levels B through G = indices 1..7)
     let mut category_texts: Vec<String> = vec![String::new(); 7];
+    // Product group (column A), only reported in the category path
+    let mut product_group = String::new();
     let mut items: Vec<MigelItem> = Vec::new();
 
     for (row_idx, row) in range_de.rows().enumerate() {
@@ -1617,9 +1844,9 @@ pub fn parse_migel_items(path: &str) -> Result<Vec<MigelItem>, Box<dyn Error>> {
             continue; // skip header
         }
 
-        let pos_nr = cell_str(row, 7); // H = Positions-Nr.
-        let bezeichnung = cell_str(row, 9); // J = Bezeichnung
-        let limitation = cell_str(row, 10); // K = Limitation
+        let pos_nr = cell_str(row, cols_de.position_nr); // H by default
+        let bezeichnung = cell_str(row, cols_de.bezeichnung); // J by default
+        let limitation = cell_str(row, cols_de.limitation); // K by default
 
         if pos_nr.is_empty() {
             // Category header row — update hierarchy
@@ -1627,12 +1854,15 @@ pub fn parse_migel_items(path: &str) -> Result<Vec<MigelItem>, Box<dyn Error>> {
                 let val = cell_str(row, i);
                 if !val.is_empty() {
                     category_texts[i] = bezeichnung.lines().next().unwrap_or("").trim().to_string();
-                    for j in (i + 1)..7 {
-                        category_texts[j] = String::new();
+                    for text in category_texts.iter_mut().skip(i + 1) {
+                        text.clear();
                     }
                     break;
                 }
             }
+            if !cell_str(row, 0).is_empty() {
+                product_group = bezeichnung.lines().next().unwrap_or("").trim().to_string();
+            }
         } else {
             // Item with position number
             let first_line = bezeichnung.lines().next().unwrap_or("").trim().to_string();
@@ -1645,6 +1875,11 @@ pub fn parse_migel_items(path: &str) -> Result<Vec<MigelItem>, Box<dyn Error>> {
             // Category hierarchy keywords (from parent categories, >= 8 chars)
             // e.g., "Injektions- und Infusionsmaterialien" → ["injektions", "infusionsmaterialien"]
             // Only long, specific terms to avoid generic matches
+            let category: Vec<String> = std::iter::once(&product_group)
+                .chain(&category_texts)
+                .filter(|t| !t.is_empty())
+                .cloned()
+                .collect();
             let cat_text = category_texts
                 .iter()
                 .filter(|t| !t.is_empty())
@@ -1679,6 +1914,7 @@ pub fn parse_migel_items(path: &str) -> Result<Vec<MigelItem>, Box<dyn Error>> {
                 secondary_fr: Vec::new(),
                 secondary_it: Vec::new(),
                 category_de,
+                category,
                 all_keywords: all_kw,
                 dims,
             });
@@ -1692,16 +1928,17 @@ pub fn parse_migel_items(path: &str) -> Result<Vec<MigelItem>, Box<dyn Error>> {
         .map(|(i, item)| (item.position_nr.clone(), i))
         .collect();
 
-    for sheet_idx in 1..sheet_names.len().min(3) {
-        let range = workbook.worksheet_range(&sheet_names[sheet_idx])?;
+    for (sheet_idx, sheet_name) in sheet_names.iter().enumerate().take(3).skip(1) {
+        let range = workbook.worksheet_range(sheet_name)?;
+        let cols = sheet_columns(&range, sheet_idx, sheet_name);
         for (row_idx, row) in range.rows().enumerate() {
             if row_idx == 0 {
                 continue;
             }
-            let pos_nr = cell_str(row, 7);
+            let pos_nr = cell_str(row, cols.position_nr);
             if let Some(&item_idx) = pos_map.get(&pos_nr) {
-                let bezeichnung = cell_str(row, 9);
-                let limitation = cell_str(row, 10);
+                let bezeichnung = cell_str(row, cols.bezeichnung);
+                let limitation = cell_str(row, cols.limitation);
                 // Primary scoring keywords: first line only
                 let kw = extract_keywords(&bezeichnung);
                 // Secondary keywords: long keywords from additional lines
@@ -1749,6 +1986,72 @@ pub struct MigelSearchIndex {
     route_groups: Vec<RouteGroup>,
     /// item index → index into `route_groups` (only for items in a group).
     item_group: HashMap<usize, usize>,
+    /// Rescore with edit-distance word matching when nothing passes
+    /// (`--migel-fuzzy`); off by default.
+    fuzzy_tier: bool,
+    /// Weights of keywords found in the device/model name
+    /// (`--weight-device`/`--weight-model`); neutral by default.
+    source_weights: SourceWeights,
+    /// Also match FR/IT keywords against the singular/plural forms of the
+    /// product words (`--migel-romance-fuzzy`); off by default.
+    romance_fuzzy: bool,
+}
+
+impl MigelSearchIndex {
+    /// Enable the Levenshtein fallback tier in [`find_best_migel_match`].
+    pub fn enable_fuzzy_tier(&mut self) {
+        self.fuzzy_tier = true;
+    }
+
+    /// Weight keywords by the product name they were found in; only the
+    /// `_with` matchers, which get the names, apply it.
+    pub fn set_source_weights(&mut self, weights: SourceWeights) {
+        self.source_weights = weights;
+    }
+
+    /// Enable the FR/IT plural fallback tier in [`find_best_migel_match`],
+    /// see [`with_romance_plurals`].
+    pub fn enable_romance_fuzzy(&mut self) {
+        self.romance_fuzzy = true;
+    }
+}
+
+/// How much a matched keyword counts toward the score when it occurs in
+/// the product's device or model name (both: the larger weight); keywords
+/// found elsewhere count 1. Scales the keyword's length in both the
+/// threshold score and the IDF ranking score, numerator and total alike,
+/// so a weight shifts the share of a keyword without pushing the score
+/// past one. Neutral by default: no gain has been measured on the golden
+/// set (which has no separate device/model names), so tune them with
+/// `--migel-eval` against a gold file first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SourceWeights {
+    pub device: f64,
+    pub model: f64,
+}
+
+impl Default for SourceWeights {
+    fn default() -> Self {
+        SourceWeights {
+            device: 1.0,
+            model: 1.0,
+        }
+    }
+}
+
+/// A product for the `_with` matchers: what [`find_best_migel_match`]
+/// takes, plus the device and model name (already part of the
+/// descriptions) so [`SourceWeights`] can tell where a keyword came from.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MigelProduct<'a> {
+    pub desc_de: &'a str,
+    pub desc_fr: &'a str,
+    pub desc_it: &'a str,
+    pub brand: &'a str,
+    pub device_type: &'a str,
+    pub risk_class: &'a str,
+    pub device_name: &'a str,
+    pub model_name: &'a str,
 }
 
 /// Build an Aho-Corasick search index for fast candidate finding.
@@ -1770,7 +2073,7 @@ pub fn build_search_index(
     let mut idf_weights: HashMap<String, f64> = HashMap::new();
     for (keyword, item_indices) in &keyword_to_items {
         let df = item_indices.len() as f64;
-        let idf = (n / df).ln().max(0.1).min(5.0);
+        let idf = (n / df).ln().clamp(0.1, 5.0);
         idf_weights.insert(keyword.clone(), idf);
     }
 
@@ -1816,6 +2119,9 @@ pub fn build_search_index(
         idf_weights,
         route_groups,
         item_group,
+        fuzzy_tier: false,
+        source_weights: SourceWeights::default(),
+        romance_fuzzy: false,
     })
 }
 
@@ -1826,6 +2132,57 @@ fn split_words(text: &str) -> Vec<&str> {
         .collect()
 }
 
+/// Language of [`with_romance_plurals`].
+#[derive(Clone, Copy)]
+enum Romance {
+    French,
+    Italian,
+}
+
+/// Minimum length (in chars) of a FR/IT word that gets plural variants.
+const ROMANCE_MIN_LEN: usize = 7;
+
+/// `text` followed by the singular/plural variants of its FR/IT words of
+/// at least [`ROMANCE_MIN_LEN`] chars (`--migel-romance-fuzzy`): French
+/// drops or adds a trailing s/x (bandages ↔ bandage), Italian swaps the
+/// final vowel (cateteri ↔ catetere, compressioni ↔ compressione, calze ↔
+/// calza). Variants are whole words for [`word_match`], which keeps suffix
+/// and truncation matching off for FR/IT, so a keyword still never matches
+/// inside a longer word ("pression" in "compressione").
+fn with_romance_plurals(text: &str, lang: Romance) -> String {
+    let mut out = text.to_string();
+    for word in split_words(text) {
+        if word.chars().count() < ROMANCE_MIN_LEN {
+            continue;
+        }
+        let variants: Vec<String> = match lang {
+            Romance::French => match word.strip_suffix(['s', 'x']) {
+                Some(singular) => vec![singular.to_string()],
+                None => vec![format!("{}s", word)],
+            },
+            Romance::Italian => {
+                let endings: &[char] = match word.chars().last() {
+                    Some('o') => &['i'],
+                    Some('a') => &['e'],
+                    Some('i') => &['o', 'e'],
+                    Some('e') => &['a', 'i'],
+                    _ => &[],
+                };
+                // Only reached for an ASCII final vowel, so the slice is safe
+                endings
+                    .iter()
+                    .map(|e| format!("{}{}", &word[..word.len() - 1], e))
+                    .collect()
+            }
+        };
+        for variant in variants {
+            out.push(' ');
+            out.push_str(&variant);
+        }
+    }
+    out
+}
+
 /// Common German compound word components that can be extracted as prefixes.
 /// Maps prefix → minimum remaining length (to prevent false splits).
 const COMPOUND_PREFIXES: &[(&str, usize)] = &[
@@ -1870,9 +2227,14 @@ fn decompose_compound(word: &str) -> Option<(&str, &str)> {
 ///   (e.g., "katheter" in "verweilkatheter"). Only for German.
 /// - `fuzzy`: if true, also tries keyword truncated by 1 char (German plural/case).
 ///   Only for German.
+///
+/// - `edits`: if true, also accepts a word of >= 6 chars within a small edit
+///   distance of the keyword (see [`max_edits`]). Only used by the
+///   `--migel-fuzzy` fallback tier, and only for German.
+///
 /// FR/IT should use suffix=false, fuzzy=false to prevent cross-type matches
 /// (e.g., "prothese" in "endoprothese" matching eye prosthesis).
-fn word_match(text_words: &[&str], keyword: &str, suffix: bool, fuzzy: bool) -> bool {
+fn word_match(text_words: &[&str], keyword: &str, suffix: bool, fuzzy: bool, edits: bool) -> bool {
     for word in text_words {
         // Exact word match
         if *word == keyword {
@@ -1908,26 +2270,73 @@ fn word_match(text_words: &[&str], keyword: &str, suffix: bool, fuzzy: bool) ->
             }
         }
     }
+    if edits {
+        if let Some(max) = max_edits(keyword) {
+            return text_words
+                .iter()
+                .any(|word| max_edits(word).is_some() && within_edit_distance(word, keyword, max));
+        }
+    }
     false
 }
 
+/// Edit distance tolerated by the fuzzy tier: 1 for words of 6–9 chars,
+/// 2 from 10 chars on; `None` for shorter words, which are never matched
+/// fuzzily.
+fn max_edits(word: &str) -> Option<usize> {
+    match word.chars().count() {
+        0..=5 => None,
+        6..=9 => Some(1),
+        _ => Some(2),
+    }
+}
+
+/// Whether the Levenshtein distance between `a` and `b` is at most `max`.
+fn within_edit_distance(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        // Every later row is >= this row's minimum, so stop early.
+        if cur.iter().min().is_some_and(|&m| m > max) {
+            return false;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()] <= max
+}
+
 /// Compute keyword overlap score using word-level matching.
 /// Returns (score, max_matched_keyword_len, matched_count, idf_score).
 /// - score: length-weighted ratio (used for threshold decisions)
 /// - idf_score: IDF-weighted ratio (used for ranking among passing candidates)
+///
+/// Each keyword's length is scaled by `weight` (see [`SourceWeights`]) in
+/// both the matched and the total sum, so the ratios stay within 0..=1.
 fn keyword_score(
     text_words: &[&str],
     keywords: &[String],
     suffix: bool,
     fuzzy: bool,
+    edits: bool,
     idf: &HashMap<String, f64>,
+    weight: &dyn Fn(&str) -> f64,
 ) -> (f64, usize, usize, f64) {
-    let total_len: f64 = keywords.iter().map(|k| k.len() as f64).sum();
+    let total_len: f64 = keywords.iter().map(|k| k.len() as f64 * weight(k)).sum();
     let total_idf: f64 = keywords
         .iter()
         .map(|k| {
             let idf_w = idf.get(k.as_str()).copied().unwrap_or(1.0);
-            k.len() as f64 * idf_w
+            k.len() as f64 * idf_w * weight(k)
         })
         .sum();
     if total_len == 0.0 {
@@ -1938,10 +2347,11 @@ fn keyword_score(
     let mut max_matched_len = 0;
     let mut matched_count = 0;
     for kw in keywords {
-        if word_match(text_words, kw, suffix, fuzzy) {
+        if word_match(text_words, kw, suffix, fuzzy, edits) {
             let idf_w = idf.get(kw.as_str()).copied().unwrap_or(1.0);
-            matched_len += kw.len() as f64;
-            matched_idf += kw.len() as f64 * idf_w;
+            let w = weight(kw);
+            matched_len += kw.len() as f64 * w;
+            matched_idf += kw.len() as f64 * idf_w * w;
             matched_count += 1;
             if kw.len() > max_matched_len {
                 max_matched_len = kw.len();
@@ -1957,6 +2367,30 @@ fn keyword_score(
     (score, max_matched_len, matched_count, idf_score)
 }
 
+/// [`SourceWeights`] factor of a matched keyword: the larger weight of the
+/// device/model name words containing it, else 1.
+fn source_weight(
+    kw: &str,
+    device_words: &[&str],
+    model_words: &[&str],
+    weights: SourceWeights,
+    suffix: bool,
+    fuzzy: bool,
+    edits: bool,
+) -> f64 {
+    if weights == SourceWeights::default() {
+        return 1.0;
+    }
+    let in_device = word_match(device_words, kw, suffix, fuzzy, edits);
+    let in_model = word_match(model_words, kw, suffix, fuzzy, edits);
+    match (in_device, in_model) {
+        (true, true) => weights.device.max(weights.model),
+        (true, false) => weights.device,
+        (false, true) => weights.model,
+        (false, false) => 1.0,
+    }
+}
+
 /// Product keyword patterns that indicate an interventional/surgical device
 /// which should NOT match any MiGeL code. These are checked against the
 /// combined DE+FR+IT text.
@@ -2567,12 +3001,9 @@ fn detect_axis(idxs: &[usize], items: &[MigelItem]) -> Option<Axis> {
     {
         return Some(Axis::VolRange);
     }
-    for axis in [Axis::Area, Axis::Volume, Axis::Weight, Axis::Width] {
-        if varies_on(idxs, items, axis) {
-            return Some(axis);
-        }
-    }
-    None
+    [Axis::Area, Axis::Volume, Axis::Weight, Axis::Width]
+        .into_iter()
+        .find(|&axis| varies_on(idxs, items, axis))
 }
 
 /// Build the routing sub-groups: bucket positions by (parent, dimension-stripped
@@ -2696,10 +3127,59 @@ fn route_dimension(
     }
 }
 
+/// Which matcher tier picked a MiGeL item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchTier {
+    /// A curated FORCED_MATCHES pin.
+    Forced,
+    /// Word-level keyword scoring.
+    Keywords,
+    /// The `--migel-romance-fuzzy` FR/IT singular/plural fallback.
+    Plurals,
+    /// The `--migel-fuzzy` edit-distance fallback.
+    Fuzzy,
+}
+
+/// Why [`explain_migel_match`] picked an item: the tier, and the item's
+/// keywords found in the product text per language (primary, then
+/// secondary and, for DE, category keywords). Forced pins have no keywords.
+#[derive(Clone, Debug)]
+pub struct MatchExplanation {
+    pub tier: MatchTier,
+    /// Ranking score of the winner, see [`score_migel_match`].
+    pub score: f64,
+    pub de: Vec<String>,
+    pub fr: Vec<String>,
+    pub it: Vec<String>,
+}
+
+impl MatchExplanation {
+    /// One-line form for the `migel_matched_keywords` column, e.g.
+    /// `de: knie, orthesen; fr: genou` (`forced` for forced pins, with a
+    /// `(plurals)` or `(fuzzy)` suffix for the fallback tiers).
+    pub fn summary(&self) -> String {
+        if self.tier == MatchTier::Forced {
+            return "forced".to_string();
+        }
+        let mut parts: Vec<String> = [("de", &self.de), ("fr", &self.fr), ("it", &self.it)]
+            .iter()
+            .filter(|(_, kws)| !kws.is_empty())
+            .map(|(lang, kws)| format!("{}: {}", lang, kws.join(", ")))
+            .collect();
+        match self.tier {
+            MatchTier::Plurals => parts.push("(plurals)".to_string()),
+            MatchTier::Fuzzy => parts.push("(fuzzy)".to_string()),
+            _ => {}
+        }
+        parts.join("; ")
+    }
+}
+
 /// Find the best-matching MiGeL item for a product.
 /// CRITICAL: Each language's keywords are scored ONLY against the same language's
 /// product description. This prevents cross-language false positives (e.g.,
 /// French "pression" matching inside German "Kompressionsschraube").
+#[allow(clippy::too_many_arguments)]
 pub fn find_best_migel_match<'a>(
     desc_de: &str,
     desc_fr: &str,
@@ -2710,6 +3190,109 @@ pub fn find_best_migel_match<'a>(
     migel_items: &'a [MigelItem],
     search_index: &MigelSearchIndex,
 ) -> Option<&'a MigelItem> {
+    score_migel_match(
+        desc_de,
+        desc_fr,
+        desc_it,
+        brand,
+        device_type,
+        risk_class,
+        migel_items,
+        search_index,
+    )
+    .map(|(item, _)| item)
+}
+
+/// Like [`find_best_migel_match`], also returning the winner's ranking
+/// score (the IDF-based score candidates are sorted by; infinite for
+/// curated forced pins), e.g. to keep the strongest of several matches.
+#[allow(clippy::too_many_arguments)]
+pub fn score_migel_match<'a>(
+    desc_de: &str,
+    desc_fr: &str,
+    desc_it: &str,
+    brand: &str,
+    device_type: &str,
+    risk_class: &str,
+    migel_items: &'a [MigelItem],
+    search_index: &MigelSearchIndex,
+) -> Option<(&'a MigelItem, f64)> {
+    let product = MigelProduct {
+        desc_de,
+        desc_fr,
+        desc_it,
+        brand,
+        device_type,
+        risk_class,
+        ..Default::default()
+    };
+    score_migel_match_with(&product, migel_items, search_index)
+}
+
+/// [`score_migel_match`] with the product's device/model name, for the
+/// index's [`SourceWeights`].
+pub fn score_migel_match_with<'a>(
+    product: &MigelProduct,
+    migel_items: &'a [MigelItem],
+    search_index: &MigelSearchIndex,
+) -> Option<(&'a MigelItem, f64)> {
+    best_migel_match(product, migel_items, search_index, false)
+        .map(|(item, score, _)| (item, score))
+}
+
+/// Like [`find_best_migel_match`], also returning which keywords made the
+/// item win (`--migel-explain`). The keyword lookup only runs for the
+/// winner, so it costs one extra pass over that item's keywords.
+#[allow(clippy::too_many_arguments)]
+pub fn explain_migel_match<'a>(
+    desc_de: &str,
+    desc_fr: &str,
+    desc_it: &str,
+    brand: &str,
+    device_type: &str,
+    risk_class: &str,
+    migel_items: &'a [MigelItem],
+    search_index: &MigelSearchIndex,
+) -> Option<(&'a MigelItem, MatchExplanation)> {
+    let product = MigelProduct {
+        desc_de,
+        desc_fr,
+        desc_it,
+        brand,
+        device_type,
+        risk_class,
+        ..Default::default()
+    };
+    explain_migel_match_with(&product, migel_items, search_index)
+}
+
+/// [`explain_migel_match`] with the product's device/model name, for the
+/// index's [`SourceWeights`].
+pub fn explain_migel_match_with<'a>(
+    product: &MigelProduct,
+    migel_items: &'a [MigelItem],
+    search_index: &MigelSearchIndex,
+) -> Option<(&'a MigelItem, MatchExplanation)> {
+    best_migel_match(product, migel_items, search_index, true)
+        .and_then(|(item, _, explanation)| Some((item, explanation?)))
+}
+
+fn best_migel_match<'a>(
+    product: &MigelProduct,
+    migel_items: &'a [MigelItem],
+    search_index: &MigelSearchIndex,
+    explain: bool,
+) -> Option<(&'a MigelItem, f64, Option<MatchExplanation>)> {
+    let MigelProduct {
+        desc_de,
+        desc_fr,
+        desc_it,
+        brand,
+        device_type,
+        risk_class,
+        device_name,
+        model_name,
+    } = *product;
     // Step -1: curated forced matches on the RAW text (pre-enrichment, so the
     // rules can't be triggered by enrichment side effects). Highest priority:
     // these are verified brand/category-exclusive pins and deliberately outrank
@@ -2723,7 +3306,19 @@ pub fn find_best_migel_match<'a>(
         // text states a matching dimension (no-op when the target isn't a size
         // family or no dimension is stated).
         let routed = route_dimension(idx, &raw_combined, migel_items, search_index);
-        return Some(&migel_items[routed]);
+        debug!(
+            "MiGel {:?}: forced match {}",
+            desc_de.trim(),
+            migel_items[routed].position_nr
+        );
+        let explanation = explain.then(|| MatchExplanation {
+            tier: MatchTier::Forced,
+            score: f64::INFINITY,
+            de: Vec::new(),
+            fr: Vec::new(),
+            it: Vec::new(),
+        });
+        return Some((&migel_items[routed], f64::INFINITY, explanation));
     }
 
     // Step -0.5: hard metadata gate — IVD and Class III devices never reach
@@ -2744,13 +3339,38 @@ pub fn find_best_migel_match<'a>(
     let fr_is_distinct = desc_fr != desc_de;
     let it_is_distinct = desc_it != desc_de;
 
+    // FR/IT text plus singular/plural variants for the plurals tier
+    // (--migel-romance-fuzzy); empty when it is off.
+    let (fr_plurals, it_plurals) = if search_index.romance_fuzzy {
+        (
+            with_romance_plurals(&fr_lower, Romance::French),
+            with_romance_plurals(&it_lower, Romance::Italian),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
     // Combined text only for candidate finding (broad pre-filter)
     let combined = format!("{} {} {}", de_lower, fr_lower, it_lower);
+    // Extra candidates only reachable through a plural variant
+    let candidate_text = format!("{} {} {}", combined, fr_plurals, it_plurals);
 
     // Pre-split text into words for word-level matching in scoring
     let de_words = split_words(&de_lower);
     let fr_words = split_words(&fr_lower);
     let it_words = split_words(&it_lower);
+    let fr_plural_words = split_words(&fr_plurals);
+    let it_plural_words = split_words(&it_plurals);
+
+    // Device/model name words, normalized like the DE and the FR/IT text,
+    // to weight the keywords found in them (SourceWeights)
+    let weights = search_index.source_weights;
+    let device_de = normalize_german(&enrich_with_german(device_name)).to_lowercase();
+    let model_de = normalize_german(&enrich_with_german(model_name)).to_lowercase();
+    let device_lower = normalize_german(device_name).to_lowercase();
+    let model_lower = normalize_german(model_name).to_lowercase();
+    let (device_de_words, model_de_words) = (split_words(&device_de), split_words(&model_de));
+    let (device_words, model_words) = (split_words(&device_lower), split_words(&model_lower));
 
     // Step 0: Check universal exclusions (interventional/surgical devices)
     if is_universally_excluded(&combined) {
@@ -2759,7 +3379,7 @@ pub fn find_best_migel_match<'a>(
 
     // Step 1: Find candidate items via Aho-Corasick automaton (single overlapping scan)
     let mut candidates: HashSet<usize> = HashSet::new();
-    let input = Input::new(&combined);
+    let input = Input::new(&candidate_text);
     for mat in search_index.automaton.find_overlapping_iter(input) {
         for &idx in &search_index.pattern_items[mat.pattern().as_usize()] {
             candidates.insert(idx);
@@ -2770,125 +3390,221 @@ pub fn find_best_migel_match<'a>(
     // DE uses fuzzy word matching (handles German plural/case: Orthese/Orthesen)
     // FR/IT use exact word matching only
     // Secondary keywords from additional lines count as bonus matches
-    let mut passing: Vec<(usize, f64, usize, f64)> = candidates
-        .iter()
-        .filter_map(|&idx| {
-            let item = &migel_items[idx];
-            // Primary scores (first-line keywords)
-            // Skip FR/IT scoring if the product has identical text in all fields
-            // Check negative keywords before scoring
-            if is_excluded_by_negative_keywords(&combined, &item.position_nr) {
-                return None; // filtered out; tracked via passing count vs candidate count
-            }
+    // The Fuzzy tier enables the --migel-fuzzy edit-distance word matching
+    // (DE only: FR/IT texts often carry English words a couple of edits away
+    // from a keyword, e.g. "absorbent" vs "absorbants"/"assorbenti"),
+    // the Plurals tier scores FR/IT against the --migel-romance-fuzzy variants.
+    let score_candidate = |idx: usize, tier: MatchTier| -> Option<(usize, f64, usize, f64)> {
+        let item = &migel_items[idx];
+        let edits = tier == MatchTier::Fuzzy;
+        let (fr_words, it_words) = if tier == MatchTier::Plurals {
+            (&fr_plural_words, &it_plural_words)
+        } else {
+            (&fr_words, &it_words)
+        };
+        // Primary scores (first-line keywords)
+        // Skip FR/IT scoring if the product has identical text in all fields
+        // Check negative keywords before scoring
+        if is_excluded_by_negative_keywords(&combined, &item.position_nr) {
+            return None; // filtered out; tracked via passing count vs candidate count
+        }
 
-            let idf = &search_index.idf_weights;
-            let (score_de, max_len_de, count_de, idf_de) =
-                keyword_score(&de_words, &item.keywords_de, true, true, idf);
-            let (score_fr, max_len_fr, count_fr, idf_fr) = if fr_is_distinct {
-                keyword_score(&fr_words, &item.keywords_fr, false, false, idf)
-            } else {
-                (0.0, 0, 0, 0.0)
-            };
-            let (score_it, max_len_it, count_it, idf_it) = if it_is_distinct {
-                keyword_score(&it_words, &item.keywords_it, false, false, idf)
-            } else {
-                (0.0, 0, 0, 0.0)
-            };
+        let idf = &search_index.idf_weights;
+        let w_de = |kw: &str| {
+            source_weight(
+                kw,
+                &device_de_words,
+                &model_de_words,
+                weights,
+                true,
+                true,
+                edits,
+            )
+        };
+        let w_other = |kw: &str| {
+            source_weight(
+                kw,
+                &device_words,
+                &model_words,
+                weights,
+                false,
+                false,
+                false,
+            )
+        };
+        let (score_de, max_len_de, count_de, idf_de) =
+            keyword_score(&de_words, &item.keywords_de, true, true, edits, idf, &w_de);
+        let (score_fr, max_len_fr, count_fr, idf_fr) = if fr_is_distinct {
+            keyword_score(
+                fr_words,
+                &item.keywords_fr,
+                false,
+                false,
+                false,
+                idf,
+                &w_other,
+            )
+        } else {
+            (0.0, 0, 0, 0.0)
+        };
+        let (score_it, max_len_it, count_it, idf_it) = if it_is_distinct {
+            keyword_score(
+                it_words,
+                &item.keywords_it,
+                false,
+                false,
+                false,
+                idf,
+                &w_other,
+            )
+        } else {
+            (0.0, 0, 0, 0.0)
+        };
 
-            // Secondary bonus matches: only count if at least 1 primary keyword matched
-            let (_, sec_max_de, sec_count_de, _) = if count_de > 0 {
-                keyword_score(&de_words, &item.secondary_de, true, true, idf)
-            } else {
-                (0.0, 0, 0, 0.0)
-            };
-            let (_, sec_max_fr, sec_count_fr, _) = if count_fr > 0 && fr_is_distinct {
-                keyword_score(&fr_words, &item.secondary_fr, false, false, idf)
-            } else {
-                (0.0, 0, 0, 0.0)
-            };
-            let (_, sec_max_it, sec_count_it, _) = if count_it > 0 && it_is_distinct {
-                keyword_score(&it_words, &item.secondary_it, false, false, idf)
-            } else {
-                (0.0, 0, 0, 0.0)
-            };
+        // Secondary bonus matches: only count if at least 1 primary keyword matched
+        let (_, sec_max_de, sec_count_de, _) = if count_de > 0 {
+            keyword_score(&de_words, &item.secondary_de, true, true, edits, idf, &w_de)
+        } else {
+            (0.0, 0, 0, 0.0)
+        };
+        let (_, sec_max_fr, sec_count_fr, _) = if count_fr > 0 && fr_is_distinct {
+            keyword_score(
+                fr_words,
+                &item.secondary_fr,
+                false,
+                false,
+                false,
+                idf,
+                &w_other,
+            )
+        } else {
+            (0.0, 0, 0, 0.0)
+        };
+        let (_, sec_max_it, sec_count_it, _) = if count_it > 0 && it_is_distinct {
+            keyword_score(
+                it_words,
+                &item.secondary_it,
+                false,
+                false,
+                false,
+                idf,
+                &w_other,
+            )
+        } else {
+            (0.0, 0, 0, 0.0)
+        };
+
+        // Category hierarchy bonus (DE only): boosts IDF ranking but does NOT
+        // count toward the match count threshold (to prevent generic category
+        // terms from pushing weak matches over the threshold)
+        let (_, cat_max_de, _, cat_idf_de) = if count_de > 0 {
+            keyword_score(&de_words, &item.category_de, true, true, edits, idf, &w_de)
+        } else {
+            (0.0, 0, 0, 0.0)
+        };
+
+        // Total count = primary + secondary (category NOT included in count)
+        let total_de = count_de + sec_count_de;
+        let total_fr = count_fr + sec_count_fr;
+        let total_it = count_it + sec_count_it;
+        let max_de = max_len_de.max(sec_max_de).max(cat_max_de);
+        let max_fr = max_len_fr.max(sec_max_fr);
+        let max_it = max_len_it.max(sec_max_it);
+
+        // Pick the best-scoring language (by primary score for threshold)
+        let (best_score, best_max_len, best_count) = [
+            (score_de, max_de, total_de),
+            (score_fr, max_fr, total_fr),
+            (score_it, max_it, total_it),
+        ]
+        .iter()
+        .copied()
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or((0.0, 0, 0));
+
+        // Best IDF score across languages (for ranking among passing candidates)
+        // Category IDF bonus rewards matches where product text aligns with
+        // the MiGeL item's parent category (e.g., "Injektions-" for needle items)
+        let best_idf = idf_de.max(idf_fr).max(idf_it) + cat_idf_de * 0.5;
+
+        // Bidirectional bonus: reward matches where the matched keyword(s)
+        // cover a large fraction of the product's significant words.
+        // A product named "Katheterventil" matching MiGeL "Katheterventil" gets
+        // high coverage (1.0), while a 30-word surgical instrument description
+        // matching one keyword gets low coverage (~0.03).
+        let significant_words = de_words.iter().filter(|w| w.len() >= 4).count().max(1) as f64;
+        let coverage = best_count as f64 / significant_words;
+        let best_idf = best_idf + coverage * 0.3;
+
+        // Phrase matching bonus: if the MiGeL Bezeichnung (first line) appears
+        // as a substring in the product text, it's a very strong signal.
+        let bez_lower = normalize_german(&item.bezeichnung).to_lowercase();
+        let phrase_bonus = if bez_lower.len() >= 8 && de_lower.contains(&bez_lower) {
+            1.0 // strong boost for exact phrase match
+        } else {
+            0.0
+        };
+        let best_idf = best_idf + phrase_bonus;
+
+        // DE significant word count (for length penalty on verbose descriptions)
+        let de_sig_words = de_words.iter().filter(|w| w.len() >= 4).count();
+
+        // Match criteria (length-based score for stable thresholds):
+        // - 2+ matched keywords: score >= 0.3, max keyword len >= 6
+        // - 1 matched keyword: score >= 0.5, keyword len >= 8
+        // - Very long DE descriptions (15+ significant words) with single keyword:
+        //   require higher score (>= 0.7) to reduce random keyword overlap in
+        //   verbose surgical instrument descriptions
+        let passes = if best_count >= 2 {
+            best_score >= 0.3 && best_max_len >= 6
+        } else if de_sig_words >= 15 {
+            best_score >= 0.7 && best_max_len >= 8
+        } else {
+            best_score >= 0.5 && best_max_len >= 8
+        };
 
-            // Category hierarchy bonus (DE only): boosts IDF ranking but does NOT
-            // count toward the match count threshold (to prevent generic category
-            // terms from pushing weak matches over the threshold)
-            let (_, cat_max_de, _, cat_idf_de) = if count_de > 0 {
-                keyword_score(&de_words, &item.category_de, true, true, idf)
-            } else {
-                (0.0, 0, 0, 0.0)
-            };
+        if passes {
+            // Use IDF score for ranking (prefers matches on rare, specific keywords)
+            Some((idx, best_idf, best_max_len, best_score))
+        } else {
+            None
+        }
+    };
+    let mut passing: Vec<(usize, f64, usize, f64)> = candidates
+        .iter()
+        .filter_map(|&idx| score_candidate(idx, MatchTier::Keywords))
+        .collect();
 
-            // Total count = primary + secondary (category NOT included in count)
-            let total_de = count_de + sec_count_de;
-            let total_fr = count_fr + sec_count_fr;
-            let total_it = count_it + sec_count_it;
-            let max_de = max_len_de.max(sec_max_de).max(cat_max_de);
-            let max_fr = max_len_fr.max(sec_max_fr);
-            let max_it = max_len_it.max(sec_max_it);
-
-            // Pick the best-scoring language (by primary score for threshold)
-            let (best_score, best_max_len, best_count) = [
-                (score_de, max_de, total_de),
-                (score_fr, max_fr, total_fr),
-                (score_it, max_it, total_it),
-            ]
+    // Step 2b (--migel-romance-fuzzy): only when no candidate passed, rescore
+    // with the FR/IT singular/plural variants, so exact word matches keep
+    // winning (one product word can satisfy two forms of a keyword that the
+    // MiGeL text lists separately, which would otherwise reorder matches).
+    let mut tier = MatchTier::Keywords;
+    if passing.is_empty() && search_index.romance_fuzzy {
+        tier = MatchTier::Plurals;
+        passing = candidates
             .iter()
-            .copied()
-            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
-            .unwrap_or((0.0, 0, 0));
-
-            // Best IDF score across languages (for ranking among passing candidates)
-            // Category IDF bonus rewards matches where product text aligns with
-            // the MiGeL item's parent category (e.g., "Injektions-" for needle items)
-            let best_idf = idf_de.max(idf_fr).max(idf_it) + cat_idf_de * 0.5;
-
-            // Bidirectional bonus: reward matches where the matched keyword(s)
-            // cover a large fraction of the product's significant words.
-            // A product named "Katheterventil" matching MiGeL "Katheterventil" gets
-            // high coverage (1.0), while a 30-word surgical instrument description
-            // matching one keyword gets low coverage (~0.03).
-            let significant_words = de_words.iter().filter(|w| w.len() >= 4).count().max(1) as f64;
-            let coverage = best_count as f64 / significant_words;
-            let best_idf = best_idf + coverage * 0.3;
-
-            // Phrase matching bonus: if the MiGeL Bezeichnung (first line) appears
-            // as a substring in the product text, it's a very strong signal.
-            let bez_lower = normalize_german(&item.bezeichnung).to_lowercase();
-            let phrase_bonus = if bez_lower.len() >= 8 && de_lower.contains(&bez_lower) {
-                1.0 // strong boost for exact phrase match
-            } else {
-                0.0
-            };
-            let best_idf = best_idf + phrase_bonus;
-
-            // DE significant word count (for length penalty on verbose descriptions)
-            let de_sig_words = de_words.iter().filter(|w| w.len() >= 4).count();
-
-            // Match criteria (length-based score for stable thresholds):
-            // - 2+ matched keywords: score >= 0.3, max keyword len >= 6
-            // - 1 matched keyword: score >= 0.5, keyword len >= 8
-            // - Very long DE descriptions (15+ significant words) with single keyword:
-            //   require higher score (>= 0.7) to reduce random keyword overlap in
-            //   verbose surgical instrument descriptions
-            let passes = if best_count >= 2 {
-                best_score >= 0.3 && best_max_len >= 6
-            } else if de_sig_words >= 15 {
-                best_score >= 0.7 && best_max_len >= 8
-            } else {
-                best_score >= 0.5 && best_max_len >= 8
-            };
+            .filter_map(|&idx| score_candidate(idx, MatchTier::Plurals))
+            .collect();
+    }
 
-            if passes {
-                // Use IDF score for ranking (prefers matches on rare, specific keywords)
-                Some((idx, best_idf, best_max_len, best_score))
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
+    // Step 2c (--migel-fuzzy): only when no candidate passed on word-level
+    // matching, rescore the same candidates accepting near-miss spellings,
+    // so an exact match always wins over a fuzzy one.
+    if passing.is_empty() && search_index.fuzzy_tier {
+        tier = MatchTier::Fuzzy;
+        passing = candidates
+            .iter()
+            .filter_map(|&idx| score_candidate(idx, MatchTier::Fuzzy))
+            .collect();
+        if !passing.is_empty() {
+            debug!(
+                "MiGel {:?}: {} candidates passed on the fuzzy tier",
+                desc_de.trim(),
+                passing.len()
+            );
+        }
+    }
 
     // Sort by IDF score descending, then max_len descending, then position_nr
     // ascending. The final tiebreak is essential for determinism: candidates
@@ -2906,11 +3622,88 @@ pub fn find_best_migel_match<'a>(
             )
     });
 
+    if log_enabled!(Level::Debug) {
+        let top: Vec<String> = passing
+            .iter()
+            .take(3)
+            .map(|&(idx, idf, max_len, score)| {
+                format!(
+                    "{} (idf {:.3}, score {:.2}, len {})",
+                    migel_items[idx].position_nr, idf, score, max_len
+                )
+            })
+            .collect();
+        debug!(
+            "MiGel {:?}: {} candidates passed; top: {}",
+            desc_de.trim(),
+            passing.len(),
+            top.join(", ")
+        );
+    }
+
     // Return the best-ranked candidate, refined to the correctly-sized sibling
     // when the product states a matching dimension (size-aware routing).
-    passing.first().map(|&(idx, _, _, _)| {
+    passing.first().map(|&(idx, score, _, _)| {
         let routed = route_dimension(idx, &combined, migel_items, search_index);
-        &migel_items[routed]
+        // Keywords of the scored winner (a size-routed sibling shares them).
+        let explanation = explain.then(|| {
+            let item = &migel_items[idx];
+            let edits = tier == MatchTier::Fuzzy;
+            let (fr_words, it_words) = if tier == MatchTier::Plurals {
+                (&fr_plural_words, &it_plural_words)
+            } else {
+                (&fr_words, &it_words)
+            };
+            let hits = |words: &[&str], lists: &[&Vec<String>], suffix: bool, fuzzy: bool| {
+                let mut found: Vec<String> = Vec::new();
+                for (i, list) in lists.iter().enumerate() {
+                    // Secondary/category keywords only count after a primary hit
+                    if i > 0 && found.is_empty() {
+                        break;
+                    }
+                    for kw in list.iter() {
+                        // Edit distance is German-only, like the truncation
+                        if !found.contains(kw)
+                            && word_match(words, kw, suffix, fuzzy, edits && fuzzy)
+                        {
+                            found.push(kw.clone());
+                        }
+                    }
+                }
+                found
+            };
+            MatchExplanation {
+                tier,
+                score,
+                de: hits(
+                    &de_words,
+                    &[&item.keywords_de, &item.secondary_de, &item.category_de],
+                    true,
+                    true,
+                ),
+                fr: if fr_is_distinct {
+                    hits(
+                        fr_words,
+                        &[&item.keywords_fr, &item.secondary_fr],
+                        false,
+                        false,
+                    )
+                } else {
+                    Vec::new()
+                },
+                it: if it_is_distinct {
+                    hits(
+                        it_words,
+                        &[&item.keywords_it, &item.secondary_it],
+                        false,
+                        false,
+                    )
+                } else {
+                    Vec::new()
+                },
+            }
+        });
+        (&migel_items[routed], score, explanation)
     })
 }
 
@@ -2932,11 +3725,25 @@ mod tests {
     /// If this test fails after an intentional rule change: inspect every
     /// listed row, confirm each delta is intended, and regenerate the fixture
     /// from a verified run.
+    ///
+    /// Runs again with `--migel-romance-fuzzy`, with `--migel-fuzzy` and with
+    /// both: neither fallback tier may change any golden row (in particular
+    /// no expected-NONE row).
     #[test]
     fn golden_set() {
         let xlsx = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/migel.xlsx");
         let items = parse_migel_items(xlsx).expect("parse pinned MiGeL XLSX fixture");
-        let index = build_search_index(&items).expect("build search index");
+        let mut index = build_search_index(&items).expect("build search index");
+        golden_set_with(&items, &index);
+        index.enable_romance_fuzzy();
+        golden_set_with(&items, &index);
+        index.enable_fuzzy_tier();
+        golden_set_with(&items, &index);
+        index.romance_fuzzy = false;
+        golden_set_with(&items, &index);
+    }
+
+    fn golden_set_with(items: &[MigelItem], index: &MigelSearchIndex) {
         let tsv = include_str!(concat!(
             env!("CARGO_MANIFEST_DIR"),
             "/tests/fixtures/golden_set.tsv"
@@ -2959,7 +3766,7 @@ mod tests {
             let got_code = if EXCLUDED_COMPANIES.contains(&brand) {
                 "NONE".to_string()
             } else {
-                find_best_migel_match(de, fr, it, brand, dtype, risk, &items, &index)
+                find_best_migel_match(de, fr, it, brand, dtype, risk, items, index)
                     .map(|m| m.position_nr.clone())
                     .unwrap_or_else(|| "NONE".to_string())
             };
@@ -2975,11 +3782,231 @@ mod tests {
 
         assert!(
             failures.is_empty(),
-            "{} of {} golden rows failed:\n{}",
+            "{} of {} golden rows failed{}:\n{}",
             failures.len(),
             total,
+            match (index.romance_fuzzy, index.fuzzy_tier) {
+                (true, true) => " with --migel-romance-fuzzy --migel-fuzzy",
+                (true, false) => " with --migel-romance-fuzzy",
+                (false, true) => " with --migel-fuzzy",
+                (false, false) => "",
+            },
             failures.join("\n")
         );
     }
 
+    /// `--migel-romance-fuzzy` variants are whole words: an FR/IT plural
+    /// matches its singular keyword and vice versa, but a keyword never
+    /// matches inside a longer word, and short words get no variants.
+    #[test]
+    fn romance_plurals_match_whole_words_only() {
+        let it = with_romance_plurals("calze a compressione graduata", Romance::Italian);
+        let words = split_words(&it);
+        assert!(word_match(&words, "compressioni", false, false, false));
+        assert!(word_match(&words, "graduate", false, false, false));
+        assert!(!word_match(&words, "pression", false, false, false));
+        assert!(!word_match(&words, "pressione", false, false, false));
+        assert!(!word_match(&words, "calza", false, false, false));
+
+        let fr = with_romance_plurals("bas de compression medicale cathéters", Romance::French);
+        let words = split_words(&fr);
+        assert!(word_match(&words, "compressions", false, false, false));
+        assert!(word_match(&words, "cathéter", false, false, false));
+        assert!(!word_match(&words, "pression", false, false, false));
+        assert!(word_match(&words, "medicales", false, false, false));
+        assert!(!word_match(&words, "basse", false, false, false));
+    }
+
+    /// The plurals tier only runs when nothing matches exactly: a singular
+    /// FR product text reaches the item whose keywords are plural
+    /// ("pansements", "steriles").
+    #[test]
+    fn romance_plurals_tier_matches_singular_text() {
+        let xlsx = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/migel.xlsx");
+        let items = parse_migel_items(xlsx).expect("parse pinned MiGeL XLSX fixture");
+        let mut index = build_search_index(&items).expect("build search index");
+        let (de, fr, it) = ("Folienverband", "Film pansement stérile", "Folienverband");
+        let find = |index: &MigelSearchIndex| {
+            find_best_migel_match(de, fr, it, "ACME", "", "", &items, index)
+                .map(|m| m.position_nr.clone())
+        };
+        assert_eq!(find(&index), None);
+        index.enable_romance_fuzzy();
+        assert!(find(&index).is_some_and(|code| code.starts_with("35.05.10.")));
+    }
+
+    /// The edit-distance tier recovers a one-letter typo in a German
+    /// product text, but only among the candidates an exact keyword hit
+    /// found: with the typo in its only keyword a product stays unmatched.
+    #[test]
+    fn fuzzy_tier_recovers_typo() {
+        let xlsx = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/migel.xlsx");
+        let items = parse_migel_items(xlsx).expect("parse pinned MiGeL XLSX fixture");
+        let mut index = build_search_index(&items).expect("build search index");
+        let find = |index: &MigelSearchIndex, de: &str| {
+            find_best_migel_match(de, "", "", "ACME", "", "", &items, index)
+                .map(|m| m.position_nr.clone())
+        };
+        let typo = "Infusionxständer und Zubehör";
+        assert_eq!(
+            find(&index, "Infusionsständer und Zubehör").as_deref(),
+            Some("03.07.08.05.1")
+        );
+        assert_eq!(find(&index, typo), None);
+        index.enable_fuzzy_tier();
+        assert_eq!(find(&index, typo).as_deref(), Some("03.07.08.05.1"));
+        assert!(find(&index, "Sauerstoffkonzentrator").is_some());
+        assert_eq!(find(&index, "Sauerstoffkonzxntrator"), None);
+    }
+
+    /// `--weight-device`/`--weight-model` scale a keyword's length by the
+    /// name it was found in, in the matched and the total sum alike: the
+    /// score stays within 0..=1 and neutral weights leave it as is.
+    #[test]
+    fn source_weights_scale_keyword_score() {
+        let idf = HashMap::new();
+        let keywords: Vec<String> = ["katheter", "urin", "beutel"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let text = ["urin", "katheter"];
+        let weights = SourceWeights {
+            device: 0.5,
+            model: 2.0,
+        };
+        let device = |kw: &str| source_weight(kw, &["katheter"], &[], weights, false, false, false);
+        let (score, _, count, idf_score) =
+            keyword_score(&text, &keywords, false, false, false, &idf, &device);
+        assert_eq!(count, 2);
+        assert!((score - (8.0 * 0.5 + 4.0) / (8.0 * 0.5 + 4.0 + 6.0)).abs() < 1e-9);
+        assert!((idf_score - score).abs() < 1e-9);
+        let model = |kw: &str| source_weight(kw, &[], &["katheter"], weights, false, false, false);
+        let (score, _, _, _) = keyword_score(&text, &keywords, false, false, false, &idf, &model);
+        assert!((score - (8.0 * 2.0 + 4.0) / (8.0 * 2.0 + 4.0 + 6.0)).abs() < 1e-9);
+        let (score, _, _, _) =
+            keyword_score(&text, &keywords[..2], false, false, false, &idf, &model);
+        assert_eq!(score, 1.0);
+        let neutral = |_: &str| 1.0;
+        let (score, _, _, _) = keyword_score(&text, &keywords, false, false, false, &idf, &neutral);
+        assert!((score - 12.0 / 18.0).abs() < 1e-9);
+        let both = source_weight(
+            "katheter",
+            &["katheter"],
+            &["katheter"],
+            weights,
+            false,
+            false,
+            false,
+        );
+        assert_eq!(both, 2.0);
+    }
+
+    /// Header cells are found by text in any position and spelling
+    /// variant; unrecognized columns keep their default index.
+    #[test]
+    fn migel_columns_detected_by_header_text() {
+        let header = |cells: &[&str]| cells.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let (cols, missing) = MigelColumns::detect(
+            &header(&["", "Limitation", "Positions-Nr", "bezeichnung"]),
+            0,
+        );
+        assert_eq!(
+            cols,
+            MigelColumns {
+                position_nr: 2,
+                bezeichnung: 3,
+                limitation: 1,
+            }
+        );
+        assert!(missing.is_empty());
+
+        let (cols, missing) = MigelColumns::detect(&header(&["N° pos.", "Denominazione"]), 2);
+        assert_eq!((cols.position_nr, cols.bezeichnung), (0, 1));
+        assert_eq!(cols.limitation, MigelColumn::Limitation.default_index());
+        assert_eq!(missing, [MigelColumn::Limitation]);
+
+        let (cols, missing) = MigelColumns::detect(&header(&["Dénomination"]), 0);
+        assert_eq!(cols, MigelColumns::default());
+        assert_eq!(missing, MigelColumn::ALL);
+    }
+
+    /// Levenshtein bound: substitutions, insertions and deletions count one
+    /// edit each (a transposition two), on chars rather than bytes.
+    #[test]
+    fn edit_distance_bound() {
+        assert!(within_edit_distance("kompression", "kompression", 0));
+        assert!(within_edit_distance("kompression", "kompresion", 1));
+        assert!(within_edit_distance("kompression", "kompressoin", 2));
+        assert!(!within_edit_distance("kompression", "kompressoin", 1));
+        assert!(within_edit_distance("bandage", "bandagen", 1));
+        assert!(!within_edit_distance("bandage", "band", 2));
+        assert!(within_edit_distance("düse", "duse", 1));
+        assert!(within_edit_distance("", "ab", 2));
+        assert!(!within_edit_distance("", "abc", 2));
+        assert_eq!(max_edits("binde"), None);
+        assert_eq!(max_edits("schiene"), Some(1));
+        assert_eq!(max_edits("kompression"), Some(2));
+    }
+
+    /// `--migel-explain` agrees with the plain matcher on every golden row
+    /// and only lists keywords of the winning item, per language.
+    #[test]
+    fn explanation_matches_winner() {
+        let xlsx = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/migel.xlsx");
+        let items = parse_migel_items(xlsx).expect("parse pinned MiGeL XLSX fixture");
+        let index = build_search_index(&items).expect("build search index");
+        let tsv = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/golden_set.tsv"
+        ));
+        let mut explained = 0;
+        for line in tsv.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+            let f: Vec<&str> = line.split('\t').collect();
+            let (de, fr, it, brand, dtype, risk) = (f[0], f[1], f[2], f[3], f[4], f[5]);
+            let plain = find_best_migel_match(de, fr, it, brand, dtype, risk, &items, &index);
+            let explained_match =
+                explain_migel_match(de, fr, it, brand, dtype, risk, &items, &index);
+            assert_eq!(
+                plain.map(|m| &m.position_nr),
+                explained_match.as_ref().map(|(m, _)| &m.position_nr)
+            );
+            let Some((item, explanation)) = explained_match else {
+                continue;
+            };
+            explained += 1;
+            if explanation.tier == MatchTier::Forced {
+                assert_eq!(explanation.summary(), "forced");
+                continue;
+            }
+            assert!(!explanation.summary().is_empty());
+            let known = |kws: &[String], pools: &[&Vec<String>]| {
+                kws.iter().all(|k| pools.iter().any(|p| p.contains(k)))
+            };
+            assert!(known(
+                &explanation.de,
+                &[&item.keywords_de, &item.secondary_de, &item.category_de]
+            ));
+            assert!(known(
+                &explanation.fr,
+                &[&item.keywords_fr, &item.secondary_fr]
+            ));
+            assert!(known(
+                &explanation.it,
+                &[&item.keywords_it, &item.secondary_it]
+            ));
+        }
+        assert!(explained > 0);
+
+        let explanation = MatchExplanation {
+            tier: MatchTier::Fuzzy,
+            score: 1.0,
+            de: vec!["knie".to_string(), "orthesen".to_string()],
+            fr: Vec::new(),
+            it: vec!["ginocchio".to_string()],
+        };
+        assert_eq!(
+            explanation.summary(),
+            "de: knie, orthesen; it: ginocchio; (fuzzy)"
+        );
+    }
 }
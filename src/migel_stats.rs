@@ -1,4 +1,5 @@
 use chrono::Local;
+use log::{info, warn};
 use plotters::prelude::*;
 use plotters::style::text_anchor::{HPos, Pos, VPos};
 use rusqlite::Connection;
@@ -6,6 +7,8 @@ use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::export::TableNames;
+
 const BG: RGBColor = RGBColor(255, 255, 255);
 const TITLE_COLOR: RGBColor = RGBColor(51, 51, 51);
 const TEXT_COLOR: RGBColor = RGBColor(85, 85, 85);
@@ -28,6 +31,9 @@ const COMPANY_COLORS: &[RGBColor] = &[
 
 const MIGEL_TOTAL_ITEMS: i64 = 786;
 
+/// A MiGeL category: (bezeichnung, count, companies sorted desc).
+pub type CategoryStat = (String, i64, Vec<(String, i64)>);
+
 pub struct Stats {
     pub total_products: i64,
     pub total_matched: i64,
@@ -36,7 +42,7 @@ pub struct Stats {
     /// All companies sorted by match count desc.
     pub company_breakdown: Vec<(String, i64)>,
     /// Top 8 MiGeL categories: (bezeichnung, count, companies sorted desc)
-    pub top_categories: Vec<(String, i64, Vec<(String, i64)>)>,
+    pub top_categories: Vec<CategoryStat>,
     /// Matches contributed by GTIN-override layer (e.g. SIGVARIS shop)
     pub override_matched: i64,
     /// Rows explicitly skipped by GTIN-override (BAG-classified non-MiGeL)
@@ -51,7 +57,7 @@ fn ch_fmt(n: i64) -> String {
         out.push('-');
     }
     for (i, b) in bytes.iter().enumerate() {
-        if i > 0 && (bytes.len() - i) % 3 == 0 {
+        if i > 0 && (bytes.len() - i).is_multiple_of(3) {
             out.push('\'');
         }
         out.push(*b as char);
@@ -59,34 +65,40 @@ fn ch_fmt(n: i64) -> String {
     out
 }
 
-pub fn read_stats(migel_db: &Path, full_db: Option<&Path>) -> Result<Stats, Box<dyn Error>> {
+pub fn read_stats(
+    migel_db: &Path,
+    full_db: Option<&Path>,
+    tables: TableNames,
+) -> Result<Stats, Box<dyn Error>> {
     let conn = Connection::open(migel_db)?;
+    let table = crate::export::quote_ident(tables.migel);
 
-    let total_matched: i64 = conn.query_row("SELECT COUNT(*) FROM swissdamed", [], |r| r.get(0))?;
+    let total_matched: i64 =
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |r| r.get(0))?;
     let num_migel_codes: i64 = conn.query_row(
-        "SELECT COUNT(DISTINCT migel_code) FROM swissdamed",
+        &format!("SELECT COUNT(DISTINCT migel_code) FROM {table}"),
         [],
         |r| r.get(0),
     )?;
     let num_companies: i64 = conn.query_row(
-        "SELECT COUNT(DISTINCT companyName) FROM swissdamed",
+        &format!("SELECT COUNT(DISTINCT companyName) FROM {table}"),
         [],
         |r| r.get(0),
     )?;
 
-    let mut stmt = conn.prepare(
-        "SELECT companyName, COUNT(*) FROM swissdamed \
-         GROUP BY companyName ORDER BY 2 DESC",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT companyName, COUNT(*) FROM {table} \
+         GROUP BY companyName ORDER BY 2 DESC"
+    ))?;
     let company_breakdown: Vec<(String, i64)> = stmt
         .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?
         .filter_map(|r| r.ok())
         .collect();
 
-    let mut stmt = conn.prepare(
-        "SELECT migel_code, migel_bezeichnung, COUNT(*) FROM swissdamed \
-         GROUP BY migel_code ORDER BY 3 DESC LIMIT 6",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT migel_code, migel_bezeichnung, COUNT(*) FROM {table} \
+         GROUP BY migel_code ORDER BY 3 DESC LIMIT 6"
+    ))?;
     let top_codes: Vec<(String, String, i64)> = stmt
         .query_map([], |r| {
             Ok((
@@ -100,10 +112,10 @@ pub fn read_stats(migel_db: &Path, full_db: Option<&Path>) -> Result<Stats, Box<
 
     let mut top_categories = Vec::with_capacity(top_codes.len());
     for (code, bez, cnt) in top_codes {
-        let mut companies_stmt = conn.prepare(
-            "SELECT companyName, COUNT(*) FROM swissdamed \
-             WHERE migel_code = ?1 GROUP BY companyName ORDER BY 2 DESC",
-        )?;
+        let mut companies_stmt = conn.prepare(&format!(
+            "SELECT companyName, COUNT(*) FROM {table} \
+             WHERE migel_code = ?1 GROUP BY companyName ORDER BY 2 DESC"
+        ))?;
         let companies: Vec<(String, i64)> = companies_stmt
             .query_map([&code], |r| {
                 Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
@@ -113,49 +125,35 @@ pub fn read_stats(migel_db: &Path, full_db: Option<&Path>) -> Result<Stats, Box<
         top_categories.push((bez, cnt, companies));
     }
 
-    // Prefer the matcher's own recorded corpus size (meta.total_products) — it
+    // Prefer the matcher's own recorded corpus size (_meta.migel_rows) — it
     // is exactly the number of rows the matcher ran against on the last --migel
     // run. The dated full-product DB is only a fallback: it is NOT regenerated
     // on every --migel run, so counting a stale one (e.g. an old 49k-row DB
     // against today's 92k corpus) would nearly double the reported match %.
     // Fall back to the full DB row count only when the meta row is missing.
-    let total_products = conn
-        .query_row(
-            "SELECT value FROM meta WHERE key = 'total_products'",
-            [],
-            |r| r.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|s| s.parse::<i64>().ok())
+    let total_products = crate::export::read_meta_count(&conn, "migel_rows")
         .filter(|&n| n > 0)
         .or_else(|| {
             full_db.and_then(|p| {
                 Connection::open(p)
                     .ok()?
-                    .query_row("SELECT COUNT(*) FROM swissdamed", [], |r| r.get(0))
+                    .query_row(
+                        &format!(
+                            "SELECT COUNT(*) FROM {}",
+                            crate::export::quote_ident(tables.udi)
+                        ),
+                        [],
+                        |r| r.get(0),
+                    )
                     .ok()
             })
         })
         .unwrap_or(0);
 
-    let override_matched: i64 = conn
-        .query_row(
-            "SELECT value FROM meta WHERE key = 'override_matched'",
-            [],
-            |r| r.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(0);
-    let override_skipped: i64 = conn
-        .query_row(
-            "SELECT value FROM meta WHERE key = 'override_skipped'",
-            [],
-            |r| r.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(0);
+    let override_matched =
+        crate::export::read_meta_count(&conn, "migel_override_matched").unwrap_or(0);
+    let override_skipped =
+        crate::export::read_meta_count(&conn, "migel_override_skipped").unwrap_or(0);
 
     Ok(Stats {
         total_products,
@@ -487,7 +485,7 @@ fn update_readme(rel_path: &str) -> Result<(), Box<dyn Error>> {
     }
     if updated && new_content.trim_end() != content.trim_end() {
         fs::write(path, new_content)?;
-        eprintln!("Updated README.md -> {}", rel_path);
+        info!("Updated README.md -> {}", rel_path);
     }
     Ok(())
 }
@@ -504,9 +502,9 @@ fn cleanup_old_pngs(png_dir: &Path, keep_filename: &str) -> Result<(), Box<dyn E
                 && name_str != keep_filename
             {
                 if let Err(e) = fs::remove_file(entry.path()) {
-                    eprintln!("Could not remove old {}: {}", name_str, e);
+                    warn!("Could not remove old {}: {}", name_str, e);
                 } else {
-                    eprintln!("Removed old png/{}", name_str);
+                    info!("Removed old png/{}", name_str);
                 }
             }
         }
@@ -518,9 +516,9 @@ fn cleanup_old_pngs(png_dir: &Path, keep_filename: &str) -> Result<(), Box<dyn E
             let name_str = name.to_string_lossy();
             if name_str.starts_with("swissdamed_migel_stats_") && name_str.ends_with(".png") {
                 if let Err(e) = fs::remove_file(entry.path()) {
-                    eprintln!("Could not remove legacy {}: {}", name_str, e);
+                    warn!("Could not remove legacy {}: {}", name_str, e);
                 } else {
-                    eprintln!("Removed legacy ./{}", name_str);
+                    info!("Removed legacy ./{}", name_str);
                 }
             }
         }
@@ -532,14 +530,15 @@ pub fn generate(
     migel_db: &Path,
     full_db: Option<&Path>,
     png_dir: &Path,
+    tables: TableNames,
 ) -> Result<PathBuf, Box<dyn Error>> {
-    let stats = read_stats(migel_db, full_db)?;
+    let stats = read_stats(migel_db, full_db, tables)?;
     let timestamp = Local::now().format("%Hh%M.%d.%m.%Y").to_string();
     let out_filename = format!("swissdamed_migel_stats_{}.png", timestamp);
     fs::create_dir_all(png_dir)?;
     let out_path = png_dir.join(&out_filename);
     render(&stats, &out_path)?;
-    eprintln!("Saved {}", out_path.display());
+    info!("Saved {}", out_path.display());
     let rel_for_readme = format!("png/{}", out_filename);
     update_readme(&rel_for_readme)?;
     cleanup_old_pngs(png_dir, &out_filename)?;
@@ -550,11 +549,11 @@ pub fn generate(
     if Path::new("Cargo.toml").exists() && Path::new("png").is_dir() {
         let repo_path = Path::new("png").join(&out_filename);
         if let Err(e) = fs::copy(&out_path, &repo_path) {
-            eprintln!("Could not mirror PNG into repo png/: {}", e);
+            warn!("Could not mirror PNG into repo png/: {}", e);
         } else {
-            eprintln!("Mirrored to {}", repo_path.display());
+            info!("Mirrored to {}", repo_path.display());
             if let Err(e) = cleanup_old_pngs(Path::new("png"), &out_filename) {
-                eprintln!("Could not prune old repo PNGs: {}", e);
+                warn!("Could not prune old repo PNGs: {}", e);
             }
         }
     }
@@ -563,31 +562,25 @@ pub fn generate(
 }
 
 /// Find the MiGeL match DB (fixed `swissdamed_migel.db`, or a legacy dated
-/// `swissdamed_migel_*.db`) and the latest full `swissdamed_<date>.db` in the
-/// app data dir's `db/` subdirectory.
+/// `swissdamed_migel_*.db`) and the latest full `swissdamed` DB named per
+/// `--name-template` in the output root's `db/` subdirectory.
 pub fn find_latest_dbs(db_dir: &Path) -> (Option<PathBuf>, Option<PathBuf>) {
     let mut migel_dbs: Vec<PathBuf> = Vec::new();
-    let mut full_dbs: Vec<PathBuf> = Vec::new();
     if let Ok(entries) = fs::read_dir(db_dir) {
         for e in entries.flatten() {
             let name = e.file_name().to_string_lossy().into_owned();
-            if name.ends_with(".db") {
-                if name == "swissdamed_migel.db" || name.starts_with("swissdamed_migel_") {
-                    // Fixed name (current) OR legacy dated files (backward compat)
-                    migel_dbs.push(e.path());
-                } else if name.starts_with("swissdamed_")
-                    && name
-                        .trim_start_matches("swissdamed_")
-                        .chars()
-                        .next()
-                        .map(|c| c.is_ascii_digit())
-                        .unwrap_or(false)
-                {
-                    full_dbs.push(e.path());
-                }
+            if name.ends_with(".db")
+                && (name == "swissdamed_migel.db" || name.starts_with("swissdamed_migel_"))
+            {
+                // Fixed name (current) OR legacy dated files (backward compat)
+                migel_dbs.push(e.path());
             }
         }
     }
+    let full_dbs: Vec<PathBuf> = crate::export::find_dated_outputs(db_dir, "swissdamed", "db")
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
     let pick_latest = |mut v: Vec<PathBuf>| -> Option<PathBuf> {
         v.sort_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok());
         v.pop()
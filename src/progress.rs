@@ -0,0 +1,54 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set once from `--quiet`; read by every long-running loop.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Suppress progress bars (log lines are filtered by the log level).
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// True if progress should be drawn as a bar: stderr is a terminal and
+/// `--quiet` is off. Otherwise callers fall back to plain log lines.
+pub fn interactive() -> bool {
+    !is_quiet() && std::io::stderr().is_terminal()
+}
+
+/// Bar counting `unit` towards `total`, or a spinner with a running count
+/// when the total is unknown. `None` when not [`interactive`].
+pub fn bar(label: &str, unit: &str, total: Option<u64>) -> Option<ProgressBar> {
+    if !interactive() {
+        return None;
+    }
+    let pb = match total {
+        Some(n) => {
+            let pb = ProgressBar::new(n);
+            pb.set_style(
+                ProgressStyle::with_template(&format!(
+                    "[{}] {{bar:40}} {{pos}}/{{len}} {} ({{eta}}) {{msg}}",
+                    label, unit
+                ))
+                .expect("valid progress template"),
+            );
+            pb
+        }
+        None => {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(
+                ProgressStyle::with_template(&format!(
+                    "[{}] {{spinner}} {{pos}} {} {{msg}}",
+                    label, unit
+                ))
+                .expect("valid progress template"),
+            );
+            pb
+        }
+    };
+    Some(pb)
+}
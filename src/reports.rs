@@ -1,3 +1,4 @@
+use log::{info, warn};
 use rayon::prelude::*;
 use serde_json::Value;
 use std::collections::{BTreeSet, HashMap, HashSet};
@@ -7,7 +8,10 @@ use crate::download::*;
 use crate::error_report::{is_valid_srn, write_srn_error_report, InvalidSrn};
 use crate::export::*;
 use crate::gdrive::{gdrive_upload_csv, send_email_with_attachment};
-use crate::migel::{build_search_index, find_best_migel_match, parse_migel_items, MigelItem};
+use crate::migel::{
+    build_search_index, explain_migel_match_with, parse_migel_items, score_migel_match_with,
+    MigelItem, MigelProduct, SourceWeights,
+};
 use crate::Args;
 
 // --- Shared helpers ---
@@ -41,14 +45,113 @@ fn find_ch_rep_uids(actor_values: &[Value], require_ar: bool) -> HashSet<String>
         .collect()
 }
 
+/// Load the UDI items from `--file` (one or more dumps) or download them
+/// (saving the raw items for `--save-json` unless `--dry-run`), check item shapes
+/// (`--strict`), apply `--dedup`/`--dedup-key`, then the item filters
+/// (`--status`, `--company`, `--risk-class`).
+pub fn load_or_download(args: &Args) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let mut filter = ItemFilter::new(&args.filter_options())?;
+    let mut values = if !args.file.is_empty() {
+        load_json_files(&args.file, args.input_format, args.limit)?
+    } else {
+        let started = std::time::Instant::now();
+        let values = download_pages_from(&args.base_url, "UDI", args.page_size, args.limit)?;
+        crate::manifest::record_download(started.elapsed());
+        if let Some(path) = args.save_json.as_ref().filter(|_| !args.dry_run) {
+            save_json_file(path, &values)?;
+            info!(
+                "Raw JSON saved: {} ({} items)",
+                path.display(),
+                values.len()
+            );
+        }
+        values
+    };
+
+    let mut shapes = ShapeCheck::new(args.strict);
+    for item in &values {
+        shapes.check(item)?;
+    }
+    shapes.report();
+
+    if let Some(key) = args.dedup_field() {
+        let dropped;
+        (values, dropped) = dedup_by_key(values, key);
+        info!(
+            "Dedup by {}: dropped {} duplicates, {} items remain.",
+            key,
+            dropped.len(),
+            values.len()
+        );
+        if !dropped.is_empty() {
+            let mut examples: Vec<&str> = Vec::new();
+            for k in &dropped {
+                if examples.len() == 5 {
+                    break;
+                }
+                if !examples.contains(&k.as_str()) {
+                    examples.push(k);
+                }
+            }
+            info!("  e.g. {}", examples.join(", "));
+        }
+    }
+
+    if filter.is_active() {
+        values.retain(|item| filter.keep(item));
+        filter.report();
+    }
+
+    Ok(values)
+}
+
+/// Hand the UDI items to `f` one at a time. `--file` inputs are streamed so
+/// a large dump is never held in memory whole; downloads and deduplication
+/// (which needs every item) go through `load_or_download`.
+pub fn for_each_input_item(
+    args: &Args,
+    f: &mut ItemSink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !args.file.is_empty() && args.dedup_field().is_none() {
+        let mut shapes = ShapeCheck::new(args.strict);
+        let mut filter = ItemFilter::new(&args.filter_options())?;
+        for_each_item_in_files(&args.file, args.input_format, args.limit, &mut |item| {
+            shapes.check(&item)?;
+            if filter.keep(&item) {
+                f(item)?;
+            }
+            Ok(())
+        })?;
+        shapes.report();
+        filter.report();
+        return Ok(());
+    }
+    for item in load_or_download(args)? {
+        f(item)?;
+    }
+    Ok(())
+}
+
+/// `_meta` of a report DB: the API endpoints it downloaded, its item count
+/// and the rest of [`Args::harvest_meta`] (invocation, tool version).
+fn report_meta(args: &Args, sources: &[&str], page_size: u32, item_count: usize) -> HarvestMeta {
+    HarvestMeta {
+        source_url: Some(sources.join(", ")),
+        page_size: Some(page_size),
+        item_count: Some(item_count),
+        ..args.harvest_meta()
+    }
+}
+
 /// Common output pattern: write CSV (optionally upload to GDrive / email) and SQLite.
 fn output_results(
     headers: &[String],
     rows: &[Vec<String>],
     name: &str,
+    meta: &HarvestMeta,
     args: &Args,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let (do_csv, do_sqlite) = if !args.csv && !args.sqlite {
+    let (do_csv, do_sqlite) = if !args.format_selected() {
         (true, true)
     } else {
         (args.csv, args.sqlite)
@@ -56,8 +159,8 @@ fn output_results(
 
     if do_csv {
         let filename = output_csv(name)?;
-        write_csv(headers, rows, &filename)?;
-        eprintln!("CSV written: {}", filename);
+        let filename = write_csv_with(headers, rows, &filename, &args.csv_options())?;
+        info!("CSV written: {}", filename);
         if args.gdrive {
             gdrive_upload_csv(args, &filename)?;
         }
@@ -68,8 +171,38 @@ fn output_results(
 
     if do_sqlite {
         let filename = output_db(name)?;
-        write_sqlite_table(headers, rows, &filename, name)?;
-        eprintln!("SQLite written: {}", filename);
+        write_sqlite_table_with(headers, rows, &filename, name, meta, &args.sqlite_options())?;
+        info!("SQLite written: {}", filename);
+        compress_db(&filename, args.compression(), args.gzip_level())?;
+    }
+
+    if args.xlsx {
+        let filename = output_xlsx(name)?;
+        write_xlsx_sheet(
+            &localize_headers(headers, args.header_lang),
+            rows,
+            &filename,
+            name,
+        )?;
+        info!("XLSX written: {}", filename);
+    }
+
+    if args.json_out {
+        let filename = output_json(name)?;
+        let filename = write_json(headers, rows, &filename, args.json_options())?;
+        info!("JSON written: {}", filename);
+    }
+
+    if args.ndjson {
+        let filename = output_ndjson(name)?;
+        let filename = write_ndjson(headers, rows, &filename, args.json_options())?;
+        info!("NDJSON written: {}", filename);
+    }
+
+    if args.parquet {
+        let filename = output_parquet(name)?;
+        write_parquet(headers, rows, &filename)?;
+        info!("Parquet written: {}", filename);
     }
 
     Ok(())
@@ -77,79 +210,281 @@ fn output_results(
 
 // --- MiGel matching ---
 
-pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    // 1. Get swissdamed data
-    let values = if let Some(ref path) = args.file {
-        eprintln!("Loading from file: {}", path.display());
-        load_json_file(path)?
-    } else {
-        download_all_pages(args.page_size)?
+/// Load a `--migel-overrides` CSV with `udiDiCode` and `migel_code`
+/// columns. Codes that are not in the MiGeL list are reported and ignored.
+fn load_migel_overrides<'a>(
+    path: &std::path::Path,
+    migel_by_pos: &HashMap<&str, &'a MigelItem>,
+) -> Result<HashMap<String, &'a MigelItem>, Box<dyn std::error::Error>> {
+    let (headers, rows) = crate::diff::read_csv_rows(path, &CsvOptions::default())?;
+    let col = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim() == name)
+            .ok_or_else(|| {
+                format!(
+                    "{}: missing column '{}' (expected udiDiCode,migel_code)",
+                    path.display(),
+                    name
+                )
+            })
     };
+    let (idx_code, idx_migel) = (col("udiDiCode")?, col("migel_code")?);
 
-    if values.is_empty() {
-        eprintln!("No data found.");
-        return Ok(());
+    let mut map = HashMap::new();
+    let mut unknown = Vec::new();
+    for row in &rows {
+        let code = row.get(idx_code).map(|s| s.trim()).unwrap_or("");
+        let migel = row.get(idx_migel).map(|s| s.trim()).unwrap_or("");
+        if code.is_empty() || migel.is_empty() {
+            continue;
+        }
+        match migel_by_pos.get(migel) {
+            Some(item) => {
+                map.insert(code.to_string(), *item);
+            }
+            None => unknown.push(format!("{} -> {}", code, migel)),
+        }
     }
-
-    let (headers, trade_name_langs) = collect_headers(&values);
-    let rows = build_rows(&values, &headers, &trade_name_langs);
-    eprintln!(
-        "Processed {} items, generated {} rows with {} columns.",
-        values.len(),
-        rows.len(),
-        headers.len()
+    info!(
+        "Loaded {} manual MiGel overrides from {}",
+        map.len(),
+        path.display()
     );
+    if !unknown.is_empty() {
+        warn!(
+            "{} overrides name a MiGeL code that is not in the list and were ignored: {}",
+            unknown.len(),
+            unknown.join(", ")
+        );
+    }
+    Ok(map)
+}
 
-    // 2. Download MiGel XLSX
-    let migel_url = "https://www.bag.admin.ch/dam/de/sd-web/77j5rwUTzbkq/Mittel-%20und%20Gegenst%C3%A4ndeliste%20per%2001.01.2026%20in%20Excel-Format.xlsx";
+/// Default MiGeL list (`--migel-url` overrides it for other years).
+pub const MIGEL_URL: &str = "https://www.bag.admin.ch/dam/de/sd-web/77j5rwUTzbkq/Mittel-%20und%20Gegenst%C3%A4ndeliste%20per%2001.01.2026%20in%20Excel-Format.xlsx";
+
+/// Download the MiGeL XLSX at `url` to `migel.xlsx` and return its path.
+pub fn download_migel_xlsx(url: &str) -> Result<String, Box<dyn std::error::Error>> {
     let migel_file = "migel.xlsx";
 
-    eprintln!("Downloading MiGel XLSX...");
+    info!("Downloading MiGel XLSX from {}...", url);
     let client = reqwest::blocking::Client::builder()
         .user_agent("swissdamed2sqlite/0.1")
         .build()?;
-    let response = client.get(migel_url).send()?;
+    let response = client.get(url).send()?;
     if !response.status().is_success() {
         return Err(format!("Failed to download MiGel XLSX: HTTP {}", response.status()).into());
     }
     let bytes = response.bytes()?;
     std::fs::write(migel_file, &bytes)?;
-    eprintln!("MiGel XLSX saved ({} bytes)", bytes.len());
+    info!("MiGel XLSX saved ({} bytes)", bytes.len());
+    Ok(migel_file.to_string())
+}
+
+/// The MiGeL XLSX to parse: `--migel-file` as is (no download), otherwise
+/// a fresh download from `--migel-url` or [`MIGEL_URL`].
+pub fn migel_xlsx_path(args: &Args) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(path) = &args.migel_file {
+        if !path.is_file() {
+            return Err(format!("MiGeL file not found: {}", path.display()).into());
+        }
+        info!("Using MiGel XLSX {}", path.display());
+        return Ok(path.to_string_lossy().into_owned());
+    }
+    download_migel_xlsx(args.migel_url.as_deref().unwrap_or(MIGEL_URL))
+}
+
+/// Column `--migel-dedup` collapses matched rows on. Rows are one per udiDi
+/// entry, so `udiDiCode` only merges repeats of the same DI (e.g. the same
+/// device listed twice), while `basicUdi` keeps one row per device family.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigelDedupKey {
+    #[value(name = "udiDiCode")]
+    UdiDiCode,
+    #[value(name = "basicUdi")]
+    BasicUdi,
+}
+
+impl MigelDedupKey {
+    pub fn column(self) -> &'static str {
+        match self {
+            MigelDedupKey::UdiDiCode => "udiDiCode",
+            MigelDedupKey::BasicUdi => "basicUdi",
+        }
+    }
+}
+
+/// Keep the highest-scoring of the matched rows sharing a `key` value, at
+/// the position of the key's first row (earlier rows win ties). Rows with
+/// an empty key are all kept.
+fn dedup_migel_matches(matches: Vec<(Vec<String>, f64)>, key: usize) -> Vec<Vec<String>> {
+    let mut best: HashMap<String, usize> = HashMap::new();
+    let mut kept: Vec<(Vec<String>, f64)> = Vec::with_capacity(matches.len());
+    for (row, score) in matches {
+        let k = row.get(key).cloned().unwrap_or_default();
+        if k.is_empty() {
+            kept.push((row, score));
+            continue;
+        }
+        match best.get(&k) {
+            Some(&i) => {
+                if score > kept[i].1 {
+                    kept[i] = (row, score);
+                }
+            }
+            None => {
+                best.insert(k, kept.len());
+                kept.push((row, score));
+            }
+        }
+    }
+    kept.into_iter().map(|(row, _)| row).collect()
+}
+
+/// Spool the `--keep-raw` items of the MiGeL database: only those whose
+/// basicUdi has a matched row (`rows` start with the UDI `headers`), to
+/// keep the DB small.
+fn spool_matched_items(
+    values: &[Value],
+    headers: &[String],
+    rows: &[Vec<String>],
+) -> Result<RawItemSpool, Box<dyn std::error::Error>> {
+    let matched_udis: HashSet<&str> = headers
+        .iter()
+        .position(|h| h == "basicUdi")
+        .map(|i| rows.iter().map(|r| r[i].as_str()).collect())
+        .unwrap_or_default();
+    let spool = RawItemSpool::new()?;
+    for item in values {
+        let basic_udi = get_field(item, "basicUdi");
+        if matched_udis.contains(basic_udi.as_str()) {
+            spool.push(&basic_udi, &item.to_string())?;
+        }
+    }
+    Ok(spool)
+}
+
+/// Item columns besides the trade names that `run_migel` reads for matching.
+const MATCH_COLUMNS: [&str; 6] = [
+    "companyName",
+    "deviceName",
+    "modelName",
+    "udiDiCode",
+    "deviceType",
+    "riskClass",
+];
+
+pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = args.migel_file.as_ref().filter(|p| !p.is_file()) {
+        return Err(format!("MiGeL file not found: {}", path.display()).into());
+    }
+    for (flag, weight) in [
+        ("--weight-device", args.weight_device),
+        ("--weight-model", args.weight_model),
+    ] {
+        if !(weight.is_finite() && weight >= 0.0) {
+            return Err(format!("{} must be a non-negative number, got {}", flag, weight).into());
+        }
+    }
+
+    // 1. Get swissdamed data
+    let values = load_or_download(args)?;
+
+    if values.is_empty() {
+        info!("No data found.");
+        return Ok(());
+    }
+
+    let builder = RowBuilder::from_values(&values, args.value_format());
+    let headers = builder.headers();
+    let rows = builder.into_rows(&headers);
+    // The matcher reads the source text as is: every trade-name language,
+    // untouched by the cell formatting options (--langs, --escape-newlines,
+    // --collapse-whitespace, ...). Only the columns it looks at are kept.
+    let raw = RowBuilder::from_values(&values, ValueFormat::DEFAULT);
+    let match_headers: Vec<String> = raw
+        .headers()
+        .into_iter()
+        .filter(|h| h.starts_with("tradeName_") || MATCH_COLUMNS.contains(&h.as_str()))
+        .collect();
+    let mut paired: Vec<(Vec<String>, Vec<String>)> = rows
+        .into_iter()
+        .zip(raw.into_rows(&match_headers))
+        .collect();
+    if !args.sort_by.is_empty() {
+        sort_rows_by(&headers, &mut paired, &args.sort_by, |(row, _)| row)?;
+    }
+    let (rows, match_rows): (Vec<Vec<String>>, Vec<Vec<String>>) = paired.into_iter().unzip();
+    info!(
+        "Processed {} items, generated {} rows with {} columns.",
+        values.len(),
+        rows.len(),
+        headers.len()
+    );
+
+    // 2. Download MiGel XLSX (or use --migel-file)
+    let migel_file = migel_xlsx_path(args)?;
 
     // 3. Parse MiGel items and build keyword index
-    eprintln!("Parsing MiGel items...");
-    let migel_items = parse_migel_items(migel_file)?;
-    eprintln!(
+    info!("Parsing MiGel items...");
+    let migel_items = parse_migel_items(&migel_file)?;
+    info!(
         "Found {} MiGel items with position numbers",
         migel_items.len()
     );
 
-    let search_index = build_search_index(&migel_items)?;
-    eprintln!("Built Aho-Corasick search index");
-
-    // 4. Find column indices for matching
-    let trade_name_indices: Vec<(String, usize)> = headers
+    let mut search_index = build_search_index(&migel_items)?;
+    if args.migel_fuzzy {
+        search_index.enable_fuzzy_tier();
+    }
+    if args.migel_romance_fuzzy {
+        search_index.enable_romance_fuzzy();
+    }
+    search_index.set_source_weights(SourceWeights {
+        device: args.weight_device,
+        model: args.weight_model,
+    });
+    info!("Built Aho-Corasick search index");
+
+    // 4. Find column indices for matching (in the raw match rows)
+    let trade_name_indices: Vec<(String, usize)> = match_headers
         .iter()
         .enumerate()
         .filter(|(_, h)| h.starts_with("tradeName_"))
         .map(|(i, h)| (h.clone(), i))
         .collect();
-    let idx_brand = headers.iter().position(|h| h == "companyName");
-    let idx_device = headers.iter().position(|h| h == "deviceName");
-    let idx_model = headers.iter().position(|h| h == "modelName");
+    let match_column = |name: &str| match_headers.iter().position(|h| h == name);
+    let idx_brand = match_column("companyName");
+    let idx_device = match_column("deviceName");
+    let idx_model = match_column("modelName");
+    let idx_dedup = match args.migel_dedup {
+        Some(key) => Some(
+            headers
+                .iter()
+                .position(|h| h == key.column())
+                .ok_or_else(|| format!("--migel-dedup: no {} column in the data", key.column()))?,
+        ),
+        None => None,
+    };
 
     // 5. Match each row against MiGel
     let mut migel_headers = headers.clone();
     migel_headers.push("migel_code".to_string());
     migel_headers.push("migel_bezeichnung".to_string());
     migel_headers.push("migel_limitation".to_string());
+    migel_headers.push("migel_category".to_string());
+    migel_headers.push("migel_match_source".to_string());
+    if args.migel_explain {
+        migel_headers.push("migel_matched_keywords".to_string());
+    }
 
     // Shared single source of truth for both CLI and GUI (src/migel.rs).
     let excluded_companies: &[&str] = crate::migel::EXCLUDED_COMPANIES;
-    let idx_company = headers.iter().position(|h| h == "companyName");
-    let idx_gtin = headers.iter().position(|h| h == "udiDiCode");
-    let idx_device_type = headers.iter().position(|h| h == "deviceType");
-    let idx_risk_class = headers.iter().position(|h| h == "riskClass");
+    let idx_company = match_column("companyName");
+    let idx_gtin = match_column("udiDiCode");
+    let idx_device_type = match_column("deviceType");
+    let idx_risk_class = match_column("riskClass");
 
     // Optional GTIN→MiGeL override map from the latest sigvaris_shop_*.db.
     // Lookup keys are both gtin14 (matches swissdamed) and gtin13. A value of
@@ -160,11 +495,11 @@ pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         match crate::sigvaris_shop::find_latest_db(&db_dir) {
             Some(p) => match crate::sigvaris_shop::load_overrides(&p) {
                 Ok(m) => {
-                    eprintln!("Loaded {} GTIN overrides from {}", m.len(), p.display());
+                    info!("Loaded {} GTIN overrides from {}", m.len(), p.display());
                     m
                 }
                 Err(e) => {
-                    eprintln!("Warning: failed to load sigvaris_shop overrides: {}", e);
+                    warn!("failed to load sigvaris_shop overrides: {}", e);
                     HashMap::new()
                 }
             },
@@ -176,15 +511,68 @@ pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         .map(|m| (m.position_nr.as_str(), m))
         .collect();
 
+    // Curated udiDiCode → MiGeL position pins (--migel-overrides); these win
+    // over everything else, including the company exclusions.
+    let manual_overrides: HashMap<String, &MigelItem> = match &args.migel_overrides {
+        Some(path) => load_migel_overrides(path, &migel_by_pos)?,
+        None => HashMap::new(),
+    };
+    let manual_hits = std::sync::atomic::AtomicUsize::new(0);
+
     let override_hits = std::sync::atomic::AtomicUsize::new(0);
     let override_skips = std::sync::atomic::AtomicUsize::new(0);
 
-    let matched_rows: Vec<Vec<String>> = rows
+    // Rows that got no MiGel item, for --migel-unmatched: (row index, row
+    // + reason + the desc_de/fr/it text the matcher saw).
+    let unmatched: std::sync::Mutex<Vec<(usize, Vec<String>)>> = Default::default();
+    let record_unmatched = |row_idx: usize, row: &[String], reason: &str, descs: [&str; 3]| {
+        if args.migel_unmatched.is_some() {
+            let mut out = row.to_vec();
+            out.push(reason.to_string());
+            out.extend(descs.iter().map(|d| d.trim().to_string()));
+            unmatched.lock().unwrap().push((row_idx, out));
+        }
+    };
+
+    let bar = crate::progress::bar("MiGel", "rows", Some(rows.len() as u64));
+    let matched_rows: Vec<(Vec<String>, f64)> = rows
         .par_iter()
-        .filter_map(|row| {
+        .zip(match_rows.par_iter())
+        .enumerate()
+        .inspect(|_| {
+            if let Some(pb) = &bar {
+                pb.inc(1);
+            }
+        })
+        .filter_map(|(row_idx, (row, raw))| {
+            // Curated sources (overrides, forced pins) score infinite so
+            // --migel-dedup always keeps them.
+            let with_item = |item: &MigelItem, source: &str, keywords: String, score: f64| {
+                let mut matched_row = row.clone();
+                matched_row.push(item.position_nr.clone());
+                matched_row.push(item.bezeichnung.clone());
+                matched_row.push(item.limitation.clone());
+                matched_row.push(item.category.join(" > "));
+                matched_row.push(source.to_string());
+                if args.migel_explain {
+                    matched_row.push(keywords);
+                }
+                (matched_row, score)
+            };
+
+            // 0. Manual override by udiDiCode
+            if let Some(item) = idx_gtin
+                .and_then(|gi| raw.get(gi))
+                .and_then(|code| manual_overrides.get(code))
+            {
+                manual_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Some(with_item(item, "override", String::new(), f64::INFINITY));
+            }
+
             if let Some(ci) = idx_company {
-                if let Some(company) = row.get(ci) {
+                if let Some(company) = raw.get(ci) {
                     if excluded_companies.contains(&company.as_str()) {
+                        record_unmatched(row_idx, row, "excluded_company", ["", "", ""]);
                         return None;
                     }
                 }
@@ -193,33 +581,35 @@ pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
             // Structured metadata for the IVD/Class-III hard gate inside
             // find_best_migel_match (applied after curated forced matches).
             let device_type = idx_device_type
-                .and_then(|i| row.get(i))
+                .and_then(|i| raw.get(i))
                 .map(String::as_str)
                 .unwrap_or("");
             let risk_class = idx_risk_class
-                .and_then(|i| row.get(i))
+                .and_then(|i| raw.get(i))
                 .map(String::as_str)
                 .unwrap_or("");
 
             // 1. Override lookup by GTIN — takes precedence over heuristic matcher
             if let Some(gi) = idx_gtin {
-                if let Some(gtin) = row.get(gi) {
+                if let Some(gtin) = raw.get(gi) {
                     if let Some(decision) = overrides.get(gtin) {
                         match decision {
                             None => {
                                 // Explicit skip (e.g. SIGVARIS Stützstrumpf / Anti-Thrombose)
                                 override_skips.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                record_unmatched(row_idx, row, "override_skip", ["", "", ""]);
                                 return None;
                             }
                             Some(code) => {
                                 if let Some(item) = migel_by_pos.get(code.as_str()) {
                                     override_hits
                                         .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                                    let mut matched_row = row.clone();
-                                    matched_row.push(item.position_nr.clone());
-                                    matched_row.push(item.bezeichnung.clone());
-                                    matched_row.push(item.limitation.clone());
-                                    return Some(matched_row);
+                                    return Some(with_item(
+                                        item,
+                                        "sigvaris_shop",
+                                        String::new(),
+                                        f64::INFINITY,
+                                    ));
                                 }
                                 // Override code not in MiGeL XLSX (stale?) — fall through to heuristic
                             }
@@ -233,7 +623,7 @@ pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
             let mut desc_it = String::new();
 
             for (col_name, idx) in &trade_name_indices {
-                let val = row.get(*idx).cloned().unwrap_or_default();
+                let val = raw.get(*idx).cloned().unwrap_or_default();
                 if val.is_empty() {
                     continue;
                 }
@@ -250,11 +640,11 @@ pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
             }
 
             let device = idx_device
-                .and_then(|i| row.get(i))
+                .and_then(|i| raw.get(i))
                 .cloned()
                 .unwrap_or_default();
             let model = idx_model
-                .and_then(|i| row.get(i))
+                .and_then(|i| raw.get(i))
                 .cloned()
                 .unwrap_or_default();
             if !device.is_empty() {
@@ -269,117 +659,388 @@ pub fn run_migel(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
             }
 
             let brand = idx_brand
-                .and_then(|i| row.get(i))
+                .and_then(|i| raw.get(i))
                 .cloned()
                 .unwrap_or_default();
 
-            find_best_migel_match(
-                &desc_de,
-                &desc_fr,
-                &desc_it,
-                &brand,
+            let product = MigelProduct {
+                desc_de: &desc_de,
+                desc_fr: &desc_fr,
+                desc_it: &desc_it,
+                brand: &brand,
                 device_type,
                 risk_class,
-                &migel_items,
-                &search_index,
-            )
-            .map(|migel| {
-                let mut matched_row = row.clone();
-                matched_row.push(migel.position_nr.clone());
-                matched_row.push(migel.bezeichnung.clone());
-                matched_row.push(migel.limitation.clone());
-                matched_row
-            })
+                device_name: &device,
+                model_name: &model,
+            };
+            let matched = if args.migel_explain {
+                explain_migel_match_with(&product, &migel_items, &search_index).map(
+                    |(migel, explanation)| {
+                        with_item(migel, "matcher", explanation.summary(), explanation.score)
+                    },
+                )
+            } else {
+                score_migel_match_with(&product, &migel_items, &search_index)
+                    .map(|(migel, score)| with_item(migel, "matcher", String::new(), score))
+            };
+            if matched.is_none() {
+                record_unmatched(row_idx, row, "no_match", [&desc_de, &desc_fr, &desc_it]);
+            }
+            matched
         })
         .collect();
+    if let Some(pb) = bar {
+        pb.finish_and_clear();
+    }
 
+    if !manual_overrides.is_empty() {
+        info!(
+            "Manual MiGel overrides applied: {} rows",
+            manual_hits.load(std::sync::atomic::Ordering::Relaxed)
+        );
+    }
     let oh = override_hits.load(std::sync::atomic::Ordering::Relaxed);
     let os = override_skips.load(std::sync::atomic::Ordering::Relaxed);
     if !overrides.is_empty() {
-        eprintln!(
+        info!(
             "GTIN overrides applied: {} matched ({} explicit-skip)",
             oh, os
         );
     }
-    eprintln!(
+    info!(
         "MiGel matches: {} out of {} rows",
         matched_rows.len(),
         rows.len()
     );
+    let matched_rows: Vec<Vec<String>> = match idx_dedup {
+        Some(key) => {
+            let before = matched_rows.len();
+            let deduped = dedup_migel_matches(matched_rows, key);
+            info!(
+                "MiGel dedup on {}: {} rows -> {}",
+                headers[key],
+                before,
+                deduped.len()
+            );
+            deduped
+        }
+        None => matched_rows.into_iter().map(|(row, _)| row).collect(),
+    };
+
+    if let Some(gold) = &args.migel_eval {
+        return evaluate_migel(
+            args,
+            gold,
+            &migel_headers,
+            &rows,
+            &matched_rows,
+            &migel_by_pos,
+        );
+    }
+
+    if args.dry_run {
+        info!("Dry run: no files written.");
+        return Ok(());
+    }
+
+    if let Some(path) = &args.migel_unmatched {
+        let mut unmatched = unmatched.into_inner().unwrap();
+        unmatched.sort_by_key(|(row_idx, _)| *row_idx);
+        let unmatched_rows: Vec<Vec<String>> = unmatched.into_iter().map(|(_, r)| r).collect();
+        let mut unmatched_headers = headers.clone();
+        for h in ["migel_unmatched_reason", "desc_de", "desc_fr", "desc_it"] {
+            unmatched_headers.push(h.to_string());
+        }
+        let filename = with_output(output_csv("swissdamed_migel_unmatched")?, Some(path))?;
+        let filename = write_csv_with(
+            &unmatched_headers,
+            &unmatched_rows,
+            &filename,
+            &args.csv_options(),
+        )?;
+        info!(
+            "Unmatched rows written: {} ({} rows)",
+            filename,
+            unmatched_rows.len()
+        );
+        crate::manifest::record_file(&filename);
+    }
 
     if matched_rows.is_empty() {
-        eprintln!("No MiGel matches found.");
+        info!("No MiGel matches found.");
         return Ok(());
     }
 
     // 6. Write matched rows to SQLite (stable filename, overwritten each run —
     //    no longer one dated DB per day)
-    let db_filename = output_db_fixed("swissdamed_migel")?;
-    write_sqlite(&migel_headers, &matched_rows, &db_filename)?;
-    eprintln!("SQLite written: {}", db_filename);
-
-    // Stash the total UDI row count + override stats in the migel DB so the
-    // stats renderer can compute coverage even when no full UDI DB is on disk.
-    {
-        let conn = rusqlite::Connection::open(&db_filename)?;
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT)",
-            [],
-        )?;
-        let total_str = rows.len().to_string();
-        let oh_str = oh.to_string();
-        let os_str = os.to_string();
-        for (k, v) in [
-            ("total_products", total_str.as_str()),
-            ("override_matched", oh_str.as_str()),
-            ("override_skipped", os_str.as_str()),
-        ] {
-            conn.execute(
-                "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
-                rusqlite::params![k, v],
-            )?;
-        }
-    }
+    let db_filename = with_output(output_db_fixed("swissdamed_migel")?, args.output.as_deref())?;
+    let meta = crate::export::HarvestMeta {
+        item_count: Some(values.len()),
+        migel: Some(crate::export::MigelMeta {
+            source: match &args.migel_file {
+                Some(path) => path.display().to_string(),
+                None => args.migel_url.as_deref().unwrap_or(MIGEL_URL).to_string(),
+            },
+            items: migel_items.len(),
+            rows: rows.len(),
+            matched: matched_rows.len(),
+            override_matched: oh,
+            override_skipped: os,
+        }),
+        ..args.harvest_meta()
+    };
+    let raw_items = match args.keep_raw {
+        true => Some(spool_matched_items(&values, &headers, &matched_rows)?),
+        false => None,
+    };
+    args.write_db(
+        &migel_headers,
+        &matched_rows,
+        &db_filename,
+        &args.migel_table,
+        &meta,
+        raw_items.as_ref(),
+    )?;
+    info!("SQLite written: {}", db_filename);
+    crate::manifest::record_table(Some(values.len()), &migel_headers, matched_rows.len());
 
     // 7. Generate stats PNG (Rust, via plotters)
-    let db_dir = crate::app_data_dir().join("db");
+    let db_dir = output_root().join("db");
     let (_, full_db) = crate::migel_stats::find_latest_dbs(&db_dir);
-    let png_dir = crate::app_data_dir().join("png");
+    let png_dir = output_root().join("png");
     let png_path = match crate::migel_stats::generate(
         std::path::Path::new(&db_filename),
         full_db.as_deref(),
         &png_dir,
+        args.table_names(),
     ) {
         Ok(p) => Some(p),
         Err(e) => {
-            eprintln!("Could not generate stats PNG: {}", e);
+            warn!("Could not generate stats PNG: {}", e);
             None
         }
     };
 
+    // The DB is final once the meta rows are in
+    crate::manifest::record_file(&db_filename);
+    if let Some(ref p) = png_path {
+        crate::manifest::record_file(&p.to_string_lossy());
+    }
+
     if args.linkedin {
         if let Some(ref p) = png_path {
-            if let Err(e) = crate::linkedin::publish_image(p, std::path::Path::new(&db_filename)) {
-                eprintln!("LinkedIn publish failed: {}", e);
+            let db = std::path::Path::new(&db_filename);
+            if let Err(e) = crate::linkedin::publish_image(p, db, &args.migel_table) {
+                warn!("LinkedIn publish failed: {}", e);
             }
         } else {
-            eprintln!("Skipping LinkedIn publish: PNG generation failed.");
+            warn!("Skipping LinkedIn publish: PNG generation failed.");
         }
     }
 
     if args.twitter {
         if let Some(ref p) = png_path {
-            if let Err(e) = crate::twitter::publish_image(p, std::path::Path::new(&db_filename)) {
-                eprintln!("Twitter publish failed: {}", e);
+            let db = std::path::Path::new(&db_filename);
+            if let Err(e) = crate::twitter::publish_image(p, db, &args.migel_table) {
+                warn!("Twitter publish failed: {}", e);
             }
         } else {
-            eprintln!("Skipping Twitter publish: PNG generation failed.");
+            warn!("Skipping Twitter publish: PNG generation failed.");
         }
     }
 
     Ok(())
 }
 
+/// Columns of the `--migel-eval-out` disagreement CSV.
+const MIGEL_EVAL_HEADERS: [&str; 7] = [
+    "udiDiCode",
+    "kind",
+    "expected_migel_code",
+    "expected_bezeichnung",
+    "predicted_migel_code",
+    "predicted_bezeichnung",
+    "migel_match_source",
+];
+
+/// Outcome counts of `--migel-eval` over the gold rows found in the data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct MigelEvalCounts {
+    correct: usize,
+    correct_none: usize,
+    wrong: usize,
+    false_match: usize,
+    miss: usize,
+}
+
+impl MigelEvalCounts {
+    /// Count one gold row (`""`: no code expected or predicted); returns
+    /// the disagreement kind, or `None` if the prediction is right.
+    fn record(&mut self, expected: &str, got: &str) -> Option<&'static str> {
+        let (count, kind) = match (expected, got) {
+            ("", "") => (&mut self.correct_none, None),
+            _ if expected == got => (&mut self.correct, None),
+            (_, "") => (&mut self.miss, Some("miss")),
+            ("", _) => (&mut self.false_match, Some("false_match")),
+            _ => (&mut self.wrong, Some("wrong")),
+        };
+        *count += 1;
+        kind
+    }
+
+    fn total(&self) -> usize {
+        self.correct + self.correct_none + self.wrong + self.false_match + self.miss
+    }
+
+    /// Precision, recall and F1; 0 where the denominator is empty.
+    fn scores(&self) -> (f64, f64, f64) {
+        let ratio = |a: usize, b: usize| if b == 0 { 0.0 } else { a as f64 / b as f64 };
+        let precision = ratio(self.correct, self.correct + self.wrong + self.false_match);
+        let recall = ratio(self.correct, self.correct + self.wrong + self.miss);
+        let f1 = if precision + recall > 0.0 {
+            2.0 * precision * recall / (precision + recall)
+        } else {
+            0.0
+        };
+        (precision, recall, f1)
+    }
+}
+
+/// `--migel-eval`: compare the matched rows with a gold CSV of
+/// udiDiCode,expected_migel_code (empty or `NONE`: no match expected) and
+/// print precision, recall and F1 to stderr. A wrong code counts as both a
+/// false positive and a miss. Gold rows whose udiDiCode is not in the data
+/// are skipped. The disagreements go to `--migel-eval-out` if given; no
+/// database is written.
+fn evaluate_migel(
+    args: &Args,
+    gold_path: &std::path::Path,
+    migel_headers: &[String],
+    rows: &[Vec<String>],
+    matched_rows: &[Vec<String>],
+    migel_by_pos: &HashMap<&str, &MigelItem>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (gold_headers, gold_rows) = crate::diff::read_csv_rows(gold_path, &CsvOptions::default())?;
+    let gold_col = |name: &str| {
+        gold_headers
+            .iter()
+            .position(|h| h.trim() == name)
+            .ok_or_else(|| {
+                format!(
+                    "{}: missing column '{}' (expected udiDiCode,expected_migel_code)",
+                    gold_path.display(),
+                    name
+                )
+            })
+    };
+    let (gi_code, gi_expected) = (gold_col("udiDiCode")?, gold_col("expected_migel_code")?);
+    let col = |name: &str| {
+        migel_headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("--migel-eval: no {} column in the data", name))
+    };
+    let (idx_code, idx_migel, idx_source) = (
+        col("udiDiCode")?,
+        col("migel_code")?,
+        col("migel_match_source")?,
+    );
+
+    let in_data: HashSet<&str> = rows
+        .iter()
+        .filter_map(|r| r.get(idx_code))
+        .map(String::as_str)
+        .collect();
+    // First match per udiDiCode (several rows only without --migel-dedup)
+    let mut predicted: HashMap<&str, (&str, &str)> = HashMap::new();
+    for row in matched_rows {
+        predicted
+            .entry(row[idx_code].as_str())
+            .or_insert((row[idx_migel].as_str(), row[idx_source].as_str()));
+    }
+    let bezeichnung = |code: &str| {
+        migel_by_pos
+            .get(code)
+            .map(|m| m.bezeichnung.clone())
+            .unwrap_or_default()
+    };
+
+    let mut counts = MigelEvalCounts::default();
+    let (mut not_in_data, mut unknown_codes) = (0, BTreeSet::new());
+    let mut disagreements = Vec::new();
+    for gold in &gold_rows {
+        let code = gold.get(gi_code).map(|s| s.trim()).unwrap_or("");
+        if code.is_empty() {
+            continue;
+        }
+        if !in_data.contains(code) {
+            not_in_data += 1;
+            continue;
+        }
+        let expected = match gold.get(gi_expected).map(|s| s.trim()).unwrap_or("") {
+            e if e.eq_ignore_ascii_case("NONE") => "",
+            e => e,
+        };
+        if !expected.is_empty() && !migel_by_pos.contains_key(expected) {
+            unknown_codes.insert(expected.to_string());
+        }
+        let (got, source) = predicted.get(code).copied().unwrap_or(("", ""));
+        let Some(kind) = counts.record(expected, got) else {
+            continue;
+        };
+        disagreements.push(vec![
+            code.to_string(),
+            kind.to_string(),
+            expected.to_string(),
+            bezeichnung(expected),
+            got.to_string(),
+            bezeichnung(got),
+            source.to_string(),
+        ]);
+    }
+    if not_in_data > 0 {
+        warn!(
+            "{} gold rows name a udiDiCode that is not in the data and were skipped",
+            not_in_data
+        );
+    }
+    if !unknown_codes.is_empty() {
+        warn!(
+            "{} expected codes are not in the MiGeL list: {}",
+            unknown_codes.len(),
+            unknown_codes.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let (precision, recall, f1) = counts.scores();
+    eprintln!(
+        "MiGeL evaluation against {} ({} gold rows in the data)",
+        gold_path.display(),
+        counts.total()
+    );
+    eprintln!("  {:<24} {:>8}", "correct match", counts.correct);
+    eprintln!("  {:<24} {:>8}", "correctly unmatched", counts.correct_none);
+    eprintln!("  {:<24} {:>8}", "wrong code", counts.wrong);
+    eprintln!("  {:<24} {:>8}", "false match", counts.false_match);
+    eprintln!("  {:<24} {:>8}", "miss", counts.miss);
+    eprintln!("  {:<24} {:>8.4}", "precision", precision);
+    eprintln!("  {:<24} {:>8.4}", "recall", recall);
+    eprintln!("  {:<24} {:>8.4}", "F1", f1);
+
+    if let Some(path) = &args.migel_eval_out {
+        let headers: Vec<String> = MIGEL_EVAL_HEADERS.iter().map(|h| h.to_string()).collect();
+        let filename = with_output(output_csv("swissdamed_migel_eval")?, Some(path))?;
+        let filename = write_csv_with(&headers, &disagreements, &filename, &args.csv_options())?;
+        info!(
+            "Disagreements written: {} ({} rows)",
+            filename,
+            disagreements.len()
+        );
+        crate::manifest::record_file(&filename);
+    }
+    Ok(())
+}
+
 // --- CH-REP only (companies with only AR/IM roles, no MF/PR) ---
 
 pub fn run_ch_rep(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
@@ -388,7 +1049,7 @@ pub fn run_ch_rep(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 
     let ch_rep_uids = find_ch_rep_uids(&actor_values, false);
 
-    eprintln!(
+    info!(
         "Found {} CH-REP only companies (AR/IM only, no MF/PR) out of all actors.",
         ch_rep_uids.len()
     );
@@ -407,13 +1068,19 @@ pub fn run_ch_rep(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     let headers = collect_flat_headers(&filtered_owned);
     let rows = build_flat_rows(&filtered_owned, &headers);
 
-    eprintln!(
+    info!(
         "CH-REP output: {} rows with {} columns.",
         rows.len(),
         headers.len()
     );
 
-    output_results(&headers, &rows, "ch_rep", args)
+    let meta = report_meta(
+        args,
+        &["https://swissdamed.ch/public/act/actors"],
+        50,
+        actor_values.len(),
+    );
+    output_results(&headers, &rows, "ch_rep", &meta, args)
 }
 
 // --- CH-REP mandate count ranking ---
@@ -427,7 +1094,7 @@ pub fn run_ch_rep_mandates(args: &Args) -> Result<(), Box<dyn std::error::Error>
     let ch_rep_uids = find_ch_rep_uids(&actor_values, args.ar_only);
 
     let mode_label = if args.ar_only { "AR-only" } else { "AR/IM" };
-    eprintln!(
+    info!(
         "Found {} CH-REP companies ({}).",
         ch_rep_uids.len(),
         mode_label
@@ -520,18 +1187,18 @@ pub fn run_ch_rep_mandates(args: &Args) -> Result<(), Box<dyn std::error::Error>
         })
         .collect();
 
-    eprintln!(
+    info!(
         "CH-REP mandate ranking: {} companies, {} total mandates.",
         rows.len(),
         ranked.iter().map(|r| r.4).sum::<u32>()
     );
 
     // Print top 20 to stderr
-    eprintln!("\nTop 20 CH-REP by mandate count:");
-    eprintln!("{:<4} {:<50} {:>6}", "Rank", "Company", "Mandates");
-    eprintln!("{}", "-".repeat(62));
+    info!("\nTop 20 CH-REP by mandate count:");
+    info!("{:<4} {:<50} {:>6}", "Rank", "Company", "Mandates");
+    info!("{}", "-".repeat(62));
     for (i, (name, _, _, _, count)) in ranked.iter().take(20).enumerate() {
-        eprintln!("{:<4} {:<50} {:>6}", i + 1, name, count);
+        info!("{:<4} {:<50} {:>6}", i + 1, name, count);
     }
 
     let name = if args.ar_only {
@@ -539,21 +1206,52 @@ pub fn run_ch_rep_mandates(args: &Args) -> Result<(), Box<dyn std::error::Error>
     } else {
         "ch_rep_mandates"
     };
-    output_results(&headers, &rows, name, args)
+    let meta = report_meta(
+        args,
+        &[
+            "https://swissdamed.ch/public/act/actors",
+            "https://swissdamed.ch/public/act/mandates",
+        ],
+        50,
+        actor_values.len() + mandate_values.len(),
+    );
+    output_results(&headers, &rows, name, &meta, args)
 }
 
-// --- Company ranking by product count ---
+// --- Dataset stats ---
 
-pub fn run_company_ranking(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let values = if let Some(ref path) = args.file {
-        eprintln!("Loading from file: {}", path.display());
-        load_json_file(path)?
+/// `--stats`: counts of the input items (file or download, with the usual
+/// filters), or of `csv_path` when given, printed as a table or `--json`.
+pub fn run_stats(
+    args: &Args,
+    csv_path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stats = if csv_path.as_os_str().is_empty() {
+        let values = load_or_download(args)?;
+        let fmt = args.value_format();
+        let (headers, trade_name_langs) = collect_headers_with(&values, &fmt);
+        let rows = build_rows_with(&values, &headers, &trade_name_langs, &fmt);
+        crate::validate::DatasetStats::from_table(Some(values.len()), &headers, &rows)
     } else {
-        download_all_pages(args.page_size)?
+        let (headers, rows) = crate::diff::read_csv_rows(csv_path, &args.csv_options())?;
+        let headers = technical_headers(&headers, detect_header_lang(&headers));
+        crate::validate::DatasetStats::from_table(None, &headers, &rows)
     };
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        stats.print_table();
+    }
+    Ok(())
+}
+
+// --- Company ranking by product count ---
+
+pub fn run_company_ranking(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let values = load_or_download(args)?;
 
     if values.is_empty() {
-        eprintln!("No data found.");
+        info!("No data found.");
         return Ok(());
     }
 
@@ -585,7 +1283,7 @@ pub fn run_company_ranking(args: &Args) -> Result<(), Box<dyn std::error::Error>
         .into_iter()
         .map(|(name, codes)| (name, codes.len()))
         .collect();
-    ranked.sort_by(|a, b| b.1.cmp(&a.1));
+    ranked.sort_by_key(|r| std::cmp::Reverse(r.1));
 
     let out_headers = vec![
         "rank".to_string(),
@@ -599,22 +1297,22 @@ pub fn run_company_ranking(args: &Args) -> Result<(), Box<dyn std::error::Error>
         .collect();
 
     let total: usize = ranked.iter().map(|(_, c)| c).sum();
-    eprintln!(
+    info!(
         "Company ranking: {} companies, {} total products.",
         ranked.len(),
         total
     );
 
-    eprintln!("\nTop 20 companies by product count:");
-    eprintln!("{:<6} {:<55} {:>8}", "Rank", "Company", "Products");
-    eprintln!("{}", "-".repeat(71));
+    info!("\nTop 20 companies by product count:");
+    info!("{:<6} {:<55} {:>8}", "Rank", "Company", "Products");
+    info!("{}", "-".repeat(71));
     for (i, (name, count)) in ranked.iter().take(20).enumerate() {
-        eprintln!("{:<6} {:<55} {:>8}", i + 1, name, count);
+        info!("{:<6} {:<55} {:>8}", i + 1, name, count);
     }
 
     let filename = output_csv("company_ranking")?;
-    write_csv(&out_headers, &out_rows, &filename)?;
-    eprintln!("CSV written: {}", filename);
+    let filename = write_csv_with(&out_headers, &out_rows, &filename, &args.csv_options())?;
+    info!("CSV written: {}", filename);
 
     if args.gdrive {
         gdrive_upload_csv(args, &filename)?;
@@ -687,7 +1385,7 @@ pub fn run_unique_srns(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         })
         .collect();
 
-    eprintln!(
+    info!(
         "Fetching details for {} AR mandates...",
         ar_mandate_ids.len()
     );
@@ -700,7 +1398,7 @@ pub fn run_unique_srns(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 
     for (i, (mid, aid)) in ar_mandate_ids.iter().enumerate() {
         if (i + 1) % 50 == 0 || i + 1 == ar_mandate_ids.len() {
-            eprintln!(
+            info!(
                 "[mandate-details] Fetching {}/{} ...",
                 i + 1,
                 ar_mandate_ids.len()
@@ -758,7 +1456,7 @@ pub fn run_unique_srns(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     let mut sorted: Vec<_> = srn_map.into_iter().collect();
     sorted.sort_by(|a, b| a.0.cmp(&b.0));
 
-    eprintln!(
+    info!(
         "Unique SRNs: {} ({} invalid filtered)",
         sorted.len(),
         invalid_srns.len()
@@ -791,8 +1489,8 @@ pub fn run_unique_srns(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         .collect();
 
     let filename = output_csv("unique_srns")?;
-    write_csv(&out_headers, &out_rows, &filename)?;
-    eprintln!("CSV written: {}", filename);
+    let filename = write_csv_with(&out_headers, &out_rows, &filename, &args.csv_options())?;
+    info!("CSV written: {}", filename);
 
     if args.gdrive {
         gdrive_upload_csv(args, &filename)?;
@@ -816,11 +1514,11 @@ pub fn run_lookup_chrn(chrn: &str, args: &Args) -> Result<(), Box<dyn std::error
         .collect();
 
     if matching_actors.is_empty() {
-        eprintln!("No actor found with actorNumber = {}", chrn);
+        info!("No actor found with actorNumber = {}", chrn);
         return Ok(());
     }
 
-    eprintln!(
+    info!(
         "Found {} actor record(s) for {}.",
         matching_actors.len(),
         chrn
@@ -851,11 +1549,11 @@ pub fn run_lookup_chrn(chrn: &str, args: &Args) -> Result<(), Box<dyn std::error
         .collect();
 
     if matching_mandate_ids.is_empty() {
-        eprintln!("No mandates found for {}.", chrn);
+        info!("No mandates found for {}.", chrn);
         return Ok(());
     }
 
-    eprintln!(
+    info!(
         "Fetching details for {} mandates of {} ...",
         matching_mandate_ids.len(),
         chrn
@@ -928,7 +1626,7 @@ pub fn run_lookup_chrn(chrn: &str, args: &Args) -> Result<(), Box<dyn std::error
         rows.push(row);
     }
 
-    eprintln!(
+    info!(
         "Joined {} mandate rows ({} columns).",
         rows.len(),
         joined_headers.len()
@@ -936,15 +1634,13 @@ pub fn run_lookup_chrn(chrn: &str, args: &Args) -> Result<(), Box<dyn std::error
 
     // Write CSV with timestamp
     let timestamp = chrono::Local::now().format("%Hh%M.%d.%m.%Y").to_string();
-    let csv_dir = crate::app_data_dir().join("csv");
-    std::fs::create_dir_all(&csv_dir)?;
-    let csv_path = csv_dir
+    let csv_path = output_subdir("csv")?
         .join(format!("{}_{}.csv", chrn, timestamp))
         .to_string_lossy()
         .to_string();
 
-    write_csv(&joined_headers, &rows, &csv_path)?;
-    eprintln!("CSV written: {}", csv_path);
+    let csv_path = write_csv_with(&joined_headers, &rows, &csv_path, &args.csv_options())?;
+    info!("CSV written: {}", csv_path);
 
     if args.gdrive {
         gdrive_upload_csv(args, &csv_path)?;
@@ -973,7 +1669,7 @@ pub fn run_ar_mandates(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         .filter(|v| v.get("actorType").and_then(|t| t.as_str()) == Some("AR"))
         .collect();
 
-    eprintln!(
+    info!(
         "Found {} AR actors out of {} total actors.",
         ar_actors.len(),
         actor_values.len()
@@ -1001,7 +1697,7 @@ pub fn run_ar_mandates(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         })
         .collect();
 
-    eprintln!(
+    info!(
         "Fetching details for {} AR mandates...",
         ar_mandate_ids.len()
     );
@@ -1063,13 +1759,22 @@ pub fn run_ar_mandates(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         rows.push(row);
     }
 
-    eprintln!(
+    info!(
         "Joined {} mandate rows for AR actors ({} columns).",
         rows.len(),
         joined_headers.len()
     );
 
-    output_results(&joined_headers, &rows, "ar_mandates", args)
+    let meta = report_meta(
+        args,
+        &[
+            "https://swissdamed.ch/public/act/actors",
+            "https://swissdamed.ch/public/act/mandates",
+        ],
+        50,
+        actor_values.len() + mandate_values.len(),
+    );
+    output_results(&joined_headers, &rows, "ar_mandates", &meta, args)
 }
 
 // --- Generic download and export (actors, mandates) ---
@@ -1085,14 +1790,14 @@ pub fn download_and_export(
     let values = download_all_pages_from(base_url, name, page_size)?;
 
     if values.is_empty() {
-        eprintln!("[{}] No data found.", name);
+        info!("[{}] No data found.", name);
         return Ok(());
     }
 
     let headers = collect_flat_headers(&values);
-    let rows = build_flat_rows(&values, &headers);
+    let rows = build_flat_rows_with(&values, &headers, &args.value_format());
 
-    eprintln!(
+    info!(
         "[{}] Processed {} items, {} rows with {} columns.",
         name,
         values.len(),
@@ -1102,8 +1807,8 @@ pub fn download_and_export(
 
     if do_csv {
         let filename = output_csv(name)?;
-        write_csv(&headers, &rows, &filename)?;
-        eprintln!("[{}] CSV written: {}", name, filename);
+        let filename = write_csv_with(&headers, &rows, &filename, &args.csv_options())?;
+        info!("[{}] CSV written: {}", name, filename);
         if args.gdrive {
             gdrive_upload_csv(args, &filename)?;
         }
@@ -1114,9 +1819,191 @@ pub fn download_and_export(
 
     if do_sqlite {
         let filename = output_db(name)?;
-        write_sqlite_table(&headers, &rows, &filename, name)?;
-        eprintln!("[{}] SQLite written: {}", name, filename);
+        write_sqlite_table_with(
+            &headers,
+            &rows,
+            &filename,
+            name,
+            &report_meta(args, &[base_url], page_size, values.len()),
+            &args.sqlite_options(),
+        )?;
+        info!("[{}] SQLite written: {}", name, filename);
+        compress_db(&filename, args.compression(), args.gzip_level())?;
+    }
+
+    if args.xlsx {
+        let filename = output_xlsx(name)?;
+        write_xlsx_sheet(
+            &localize_headers(&headers, args.header_lang),
+            &rows,
+            &filename,
+            name,
+        )?;
+        info!("[{}] XLSX written: {}", name, filename);
+    }
+
+    if args.json_out {
+        let filename = output_json(name)?;
+        let filename = write_json(&headers, &rows, &filename, args.json_options())?;
+        info!("[{}] JSON written: {}", name, filename);
+    }
+
+    if args.ndjson {
+        let filename = output_ndjson(name)?;
+        let filename = write_ndjson(&headers, &rows, &filename, args.json_options())?;
+        info!("[{}] NDJSON written: {}", name, filename);
+    }
+
+    if args.parquet {
+        let filename = output_parquet(name)?;
+        write_parquet(&headers, &rows, &filename)?;
+        info!("[{}] Parquet written: {}", name, filename);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `--migel-dedup` keeps the best-scoring row per key at the key's
+    /// first position; ties go to the earlier row, empty keys are all kept.
+    #[test]
+    fn dedup_migel_matches_keeps_best_per_key() {
+        let row = |key: &str, code: &str| vec![key.to_string(), code.to_string()];
+        let matches = vec![
+            (row("A", "1"), 0.5),
+            (row("B", "2"), 0.9),
+            (row("A", "3"), 0.8),
+            (row("", "4"), 0.1),
+            (row("B", "5"), 0.9),
+            (row("", "6"), 0.2),
+            (row("A", "7"), f64::INFINITY),
+        ];
+        let kept = dedup_migel_matches(matches, 0);
+        assert_eq!(
+            kept,
+            [row("A", "7"), row("B", "2"), row("", "4"), row("", "6")]
+        );
+    }
+
+    /// The MiGeL `--keep-raw` table keeps only the items of matched rows.
+    #[test]
+    fn spool_matched_items_keeps_matched_udis() {
+        let values: Vec<Value> = ["B1", "B2", "B3"]
+            .iter()
+            .map(|udi| serde_json::json!({ "basicUdi": udi }))
+            .collect();
+        let headers = vec!["basicUdi".to_string(), "udiDiCode".to_string()];
+        let rows = vec![
+            vec!["B3".to_string(), "0764003".to_string()],
+            vec!["B1".to_string(), "0764001".to_string()],
+        ];
+        let spool = spool_matched_items(&values, &headers, &rows).unwrap();
+        let filename = std::env::temp_dir()
+            .join(format!(
+                "swissdamed2sqlite_migel_raw_test_{}.db",
+                std::process::id()
+            ))
+            .to_string_lossy()
+            .to_string();
+        let opts = SqliteOptions {
+            raw_items: Some(&spool),
+            ..SqliteOptions::default()
+        };
+        write_sqlite_with(&headers, &rows, &filename, &HarvestMeta::default(), &opts).unwrap();
+        let udis: Vec<String> = rusqlite::Connection::open(&filename)
+            .unwrap()
+            .prepare("SELECT basicUdi FROM swissdamed_raw ORDER BY item_id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(udis, ["B1", "B3"]);
+        std::fs::remove_file(&filename).ok();
+    }
+
+    /// A wrong code costs both precision and recall, a false match only
+    /// precision, a miss only recall; correct "no match" rows neither.
+    #[test]
+    fn migel_eval_counts_score_outcomes() {
+        let mut counts = MigelEvalCounts::default();
+        let outcomes = [
+            ("1.1", "1.1", None),
+            ("1.2", "1.2", None),
+            ("1.3", "1.3", None),
+            ("", "", None),
+            ("1.4", "9.9", Some("wrong")),
+            ("", "2.1", Some("false_match")),
+            ("1.5", "", Some("miss")),
+        ];
+        for (expected, got, kind) in outcomes {
+            assert_eq!(counts.record(expected, got), kind, "{} {}", expected, got);
+        }
+        assert_eq!(
+            counts,
+            MigelEvalCounts {
+                correct: 3,
+                correct_none: 1,
+                wrong: 1,
+                false_match: 1,
+                miss: 1,
+            }
+        );
+        assert_eq!(counts.total(), 7);
+        let (precision, recall, f1) = counts.scores();
+        assert_eq!((precision, recall), (0.6, 0.6));
+        assert!((f1 - 0.6).abs() < 1e-12);
+        assert_eq!(MigelEvalCounts::default().scores(), (0.0, 0.0, 0.0));
+    }
+
+    /// Non-object entries in an input file reach no row without
+    /// `--strict` and fail the load with it, naming the first one's index,
+    /// on the streaming path and on the `--dedup` path alike.
+    #[test]
+    fn strict_input_fails_on_non_object_entries() {
+        use clap::Parser;
+        let path = std::env::temp_dir().join(format!(
+            "swissdamed2sqlite_strict_test_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#"{"values": [{"basicUdi": "B1"}, "junk", {"basicUdi": "B2"}, 7]}"#,
+        )
+        .unwrap();
+        let args = |extra: &[&str]| {
+            let file = path.to_string_lossy().to_string();
+            let mut argv = vec!["swissdamed2sqlite", "--file", &file];
+            argv.extend_from_slice(extra);
+            Args::parse_from(argv)
+        };
+
+        let mut builder = RowBuilder::new(ValueFormat::DEFAULT);
+        for_each_input_item(&args(&[]), &mut |item| {
+            builder.push(&item);
+            Ok(())
+        })
+        .unwrap();
+        let headers = builder.headers();
+        let rows = builder.into_rows(&headers);
+        assert_eq!(
+            rows.iter().map(|r| r[0].as_str()).collect::<Vec<_>>(),
+            ["B1", "B2"]
+        );
+
+        for extra in [&["--strict"][..], &["--strict", "--dedup"]] {
+            let err = for_each_input_item(&args(extra), &mut |_| Ok(()))
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                err, "Item 1: entry is not an object (--strict): \"junk\"",
+                "{:?}",
+                extra
+            );
+        }
+        std::fs::remove_file(&path).ok();
+    }
+}
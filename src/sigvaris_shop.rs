@@ -10,6 +10,7 @@
 
 use crate::download::http_client;
 use crate::export::output_db;
+use log::{info, warn};
 use reqwest::blocking::Client;
 use rusqlite::{params, Connection};
 use serde_json::Value;
@@ -94,7 +95,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         .map(|p| load_variants_by_handle(p).unwrap_or_default())
         .unwrap_or_default();
     if baseline_variants > 0 {
-        eprintln!(
+        info!(
             "[sigvaris-shop] Baseline: {} variants in latest DB, {} known handles available \
              as resume fallback (new scrape must reach 80% to be accepted)",
             baseline_variants,
@@ -108,7 +109,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     let already_done: HashSet<String> = list_handles_in_db(&partial_path)?.into_iter().collect();
     if !already_done.is_empty() {
         let n = count_variants(&partial_path).unwrap_or(0);
-        eprintln!(
+        info!(
             "[sigvaris-shop] Resuming from {}: {} handles, {} variants already cached",
             partial_path.display(),
             already_done.len(),
@@ -117,22 +118,22 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Warmup: visit the homepage so Cloudflare seats us with a cookie
-    eprintln!("[sigvaris-shop] Warming up Cloudflare session ...");
+    info!("[sigvaris-shop] Warming up Cloudflare session ...");
     let _ = client
         .get(format!("{}/", BASE))
         .header("Accept", "text/html,application/xhtml+xml")
         .send()
         .map(|r| r.text());
 
-    eprintln!("[sigvaris-shop] Discovering product handles ...");
+    info!("[sigvaris-shop] Discovering product handles ...");
     let discovered = discover_handles(&client).unwrap_or_else(|e| {
-        eprintln!(
+        warn!(
             "[sigvaris-shop] Discovery error ({}); will rely on baseline handles",
             e
         );
         Vec::new()
     });
-    eprintln!(
+    info!(
         "[sigvaris-shop] Found {} distinct product handles via discovery",
         discovered.len()
     );
@@ -150,7 +151,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
     let mut handles: Vec<String> = all_handles.into_iter().collect();
     handles.sort();
-    eprintln!(
+    info!(
         "[sigvaris-shop] Total master handle list: {} (discovery {} + baseline {} + partial {})",
         handles.len(),
         discovered.len(),
@@ -166,7 +167,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
-    eprintln!("[sigvaris-shop] Fetching product details ...");
+    info!("[sigvaris-shop] Fetching product details ...");
     let mut new_fetches = 0usize;
     let mut errors = 0usize;
     let mut fallbacks = 0usize;
@@ -184,7 +185,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             }
             Err(e) => {
                 if let Some(cached) = baseline_cache.get(handle.as_str()) {
-                    eprintln!(
+                    warn!(
                         "[sigvaris-shop]   error on {}: {} — using {} cached variants from baseline",
                         handle,
                         e,
@@ -193,7 +194,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                     fallbacks += 1;
                     cached.clone()
                 } else {
-                    eprintln!(
+                    warn!(
                         "[sigvaris-shop]   error on {}: {} (no baseline cache)",
                         handle, e
                     );
@@ -205,7 +206,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         append_to_partial(&partial_path, handle, &variants_for_handle)?;
         if (i + 1) % 25 == 0 {
             let cur = count_variants(&partial_path).unwrap_or(0);
-            eprintln!(
+            info!(
                 "[sigvaris-shop]   {} / {} processed ({} fetched, {} fallback, {} errors, {} variants in partial)",
                 i + 1,
                 to_process.len(),
@@ -218,7 +219,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         thread::sleep(Duration::from_millis(1000));
     }
     let final_variants = count_variants(&partial_path).unwrap_or(0);
-    eprintln!(
+    info!(
         "[sigvaris-shop] Done: {} variants in partial DB ({} master handles, {} new fetches, \
          {} cache fallbacks, {} errors)",
         final_variants, total, new_fetches, fallbacks, errors
@@ -258,7 +259,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
     let db_path = output_db("sigvaris_shop")?;
     std::fs::rename(&partial_path, &db_path)?;
-    eprintln!("[sigvaris-shop] SQLite written: {}", db_path);
+    info!("[sigvaris-shop] SQLite written: {}", db_path);
 
     // Summary
     let mapped: i64 = {
@@ -269,7 +270,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             |r| r.get(0),
         )?
     };
-    eprintln!(
+    info!(
         "[sigvaris-shop] Summary: {} variants total, {} mapped to MiGeL, {} skipped (non-MiGeL)",
         final_variants,
         mapped,
@@ -291,7 +292,7 @@ fn discover_handles(client: &Client) -> Result<Vec<String>, Box<dyn std::error::
             let mut resp_opt = None;
             for wait in [0u64, 10, 30] {
                 if wait > 0 {
-                    eprintln!(
+                    info!(
                         "[sigvaris-shop]   discovery 403 on {} p{}; sleep {}s",
                         col, page, wait
                     );
@@ -349,11 +350,11 @@ fn fetch_product_with_retry(
     let backoffs_secs: [u64; 3] = [10, 30, 60];
     let mut last_err: Option<Box<dyn std::error::Error>> = None;
     for (attempt, wait) in std::iter::once(0u64)
-        .chain(backoffs_secs.into_iter())
+        .chain(backoffs_secs)
         .enumerate()
     {
         if wait > 0 {
-            eprintln!(
+            warn!(
                 "[sigvaris-shop]   retry {}/{} for {} after {}s ...",
                 attempt,
                 backoffs_secs.len(),
@@ -664,7 +665,7 @@ fn derive_migel(title: &str, product_type: &str, klasse: Option<u8>) -> (Option<
 /// Anti-Thrombose / Reisestrumpf / Klasse 1, i.e. not a MiGeL Pflichtleistung).
 pub type Overrides = HashMap<String, Option<String>>;
 
-/// Locate the most recent finalized `db/sigvaris_shop_DD.MM.YYYY.db` under
+/// Locate the most recent finalized `db/sigvaris_shop_<date>.db` under
 /// the app data dir. The in-progress partial DB (`sigvaris_shop_partial.db`)
 /// is explicitly excluded — picking it as baseline would let the 80 %
 /// threshold trivially compare today's partial against itself.
@@ -672,7 +673,7 @@ pub type Overrides = HashMap<String, Option<String>>;
 /// Date is parsed from the filename (so a manually restored older DB takes
 /// priority correctly even if mtime is fresh); ties fall back to mtime.
 pub fn find_latest_db(db_dir: &Path) -> Option<PathBuf> {
-    let mut newest: Option<(String, std::time::SystemTime, PathBuf)> = None;
+    let mut newest: Option<(chrono::NaiveDate, std::time::SystemTime, PathBuf)> = None;
     if let Ok(entries) = std::fs::read_dir(db_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
@@ -687,22 +688,20 @@ pub fn find_latest_db(db_dir: &Path) -> Option<PathBuf> {
             if name == "sigvaris_shop_partial.db" {
                 continue;
             }
-            // Extract DD.MM.YYYY → sort key YYYYMMDD; non-conforming names sort last.
+            // Extract DD.MM.YYYY or YYYY-MM-DD (--date-format) as the sort key.
             let date_part = name
                 .strip_prefix("sigvaris_shop_")
                 .and_then(|s| s.strip_suffix(".db"))
                 .unwrap_or("");
-            let sort_key = match date_part.split('.').collect::<Vec<_>>().as_slice() {
-                [d, m, y] if d.len() == 2 && m.len() == 2 && y.len() == 4 => {
-                    format!("{}{}{}", y, m, d)
-                }
-                _ => continue, // skip non-dated files
+            let sort_key = match crate::export::parse_date_stamp(date_part) {
+                Some(date) => date,
+                None => continue, // skip non-dated files
             };
             let mtime = entry
                 .metadata()
                 .and_then(|m| m.modified())
                 .unwrap_or(std::time::UNIX_EPOCH);
-            let take = newest.as_ref().map_or(true, |(k, t, _)| {
+            let take = newest.as_ref().is_none_or(|(k, t, _)| {
                 sort_key > *k || (sort_key == *k && mtime > *t)
             });
             if take {
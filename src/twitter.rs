@@ -10,6 +10,7 @@
 //! config), reusing the same OAuth 1.0a key set.
 
 use hmac::{Hmac, Mac};
+use log::info;
 use rusqlite::Connection;
 use serde::Deserialize;
 use sha1::Sha1;
@@ -56,13 +57,13 @@ fn parse_twurlrc(text: &str) -> Option<Creds> {
 
 fn load_creds() -> Result<Creds, Box<dyn Error>> {
     if let Some(p) = find_file("twitter_credentials.json") {
-        eprintln!("[twitter] Using credentials: {}", p.display());
+        info!("[twitter] Using credentials: {}", p.display());
         return Ok(serde_json::from_str(&fs::read_to_string(&p)?)?);
     }
     if let Some(home) = std::env::var_os("HOME") {
         let rc = PathBuf::from(home).join(".twurlrc");
         if rc.exists() {
-            eprintln!("[twitter] Using credentials: {}", rc.display());
+            info!("[twitter] Using credentials: {}", rc.display());
             return parse_twurlrc(&fs::read_to_string(&rc)?)
                 .ok_or_else(|| "could not parse OAuth fields from ~/.twurlrc".into());
         }
@@ -141,7 +142,7 @@ fn format_thousands(n: i64) -> String {
         out.push('-');
     }
     for (i, b) in bytes.iter().enumerate() {
-        if i > 0 && (bytes.len() - i) % 3 == 0 {
+        if i > 0 && (bytes.len() - i).is_multiple_of(3) {
             out.push('\'');
         }
         out.push(*b as char);
@@ -150,24 +151,25 @@ fn format_thousands(n: i64) -> String {
 }
 
 /// Build a compact tweet caption (well under 280 chars) from the MiGeL DB.
-fn build_caption(migel_db: &Path) -> String {
+fn build_caption(migel_db: &Path, migel_table: &str) -> String {
     let conn = match Connection::open(migel_db) {
         Ok(c) => c,
         Err(_) => return default_caption(),
     };
+    let table = crate::export::quote_ident(migel_table);
     let matched: i64 = conn
-        .query_row("SELECT COUNT(*) FROM swissdamed", [], |r| r.get(0))
+        .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |r| r.get(0))
         .unwrap_or(0);
     let codes: i64 = conn
         .query_row(
-            "SELECT COUNT(DISTINCT migel_code) FROM swissdamed",
+            &format!("SELECT COUNT(DISTINCT migel_code) FROM {table}"),
             [],
             |r| r.get(0),
         )
         .unwrap_or(0);
     let companies: i64 = conn
         .query_row(
-            "SELECT COUNT(DISTINCT companyName) FROM swissdamed",
+            &format!("SELECT COUNT(DISTINCT companyName) FROM {table}"),
             [],
             |r| r.get(0),
         )
@@ -225,7 +227,7 @@ fn upload_media(
 ) -> Result<String, Box<dyn Error>> {
     let upload_url = "https://api.twitter.com/2/media/upload";
     let bytes = fs::read(png_path)?;
-    eprintln!(
+    info!(
         "[twitter] Uploading {} ({:.1} KB)",
         png_path.display(),
         bytes.len() as f64 / 1024.0
@@ -261,7 +263,7 @@ fn upload_media(
         .or_else(|| v["id"].as_str())
         .ok_or_else(|| format!("no media id in upload response: {text}"))?
         .to_string();
-    eprintln!("[twitter] media_id: {media_id}");
+    info!("[twitter] media_id: {media_id}");
     Ok(media_id)
 }
 
@@ -292,19 +294,23 @@ fn create_tweet(
         .as_str()
         .ok_or_else(|| format!("no tweet id in response: {text}"))?
         .to_string();
-    eprintln!("[twitter] Published: https://x.com/i/web/status/{id}");
+    info!("[twitter] Published: https://x.com/i/web/status/{id}");
     Ok(id)
 }
 
 /// Upload `png_path` and post a tweet with it, using a caption derived from
 /// the MiGeL SQLite DB. Returns the tweet URL.
-pub fn publish_image(png_path: &Path, migel_db: &Path) -> Result<String, Box<dyn Error>> {
+pub fn publish_image(
+    png_path: &Path,
+    migel_db: &Path,
+    migel_table: &str,
+) -> Result<String, Box<dyn Error>> {
     let creds = load_creds()?;
     let client = reqwest::blocking::Client::builder()
         .timeout(std::time::Duration::from_secs(180))
         .build()?;
     let media_id = upload_media(&client, &creds, png_path)?;
-    let caption = build_caption(migel_db);
+    let caption = build_caption(migel_db, migel_table);
     let id = create_tweet(&client, &creds, &caption, &[media_id])?;
     Ok(format!("https://x.com/i/web/status/{id}"))
 }
@@ -0,0 +1,247 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::data::{
+    get_field, trade_name_lang, PREFERRED_TRADE_NAME, TRADE_NAME_LANGUAGE, TRADE_NAME_TEXT,
+};
+
+/// How many offending keys to list per problem in the report.
+const MAX_EXAMPLES: usize = 10;
+
+/// Data-quality summary of a UDI dump, built item by item.
+#[derive(Serialize, Default)]
+pub struct ValidationReport {
+    pub total_items: usize,
+    /// Hard problem: items with no (or an empty) `basicUdi`.
+    pub missing_basic_udi: usize,
+    /// Items whose `udiDis` is missing or an empty array.
+    pub empty_udi_dis: usize,
+    pub udi_dis_without_code: usize,
+    /// Hard problem: udiDiCodes that occur in more than one item.
+    pub duplicate_udi_di_codes: usize,
+    pub duplicate_udi_di_code_examples: Vec<String>,
+    /// Number of tradeNames entries per language.
+    pub trade_name_languages: BTreeMap<String, usize>,
+    /// First item each udiDiCode was seen in; `None` once it has been
+    /// reported as duplicate.
+    #[serde(skip)]
+    first_item_by_code: HashMap<String, Option<usize>>,
+}
+
+impl ValidationReport {
+    pub fn push(&mut self, item: &Value) {
+        let item_idx = self.total_items;
+        self.total_items += 1;
+
+        if get_field(item, "basicUdi").is_empty() {
+            self.missing_basic_udi += 1;
+        }
+
+        let udi_dis = match item.get("udiDis").and_then(|v| v.as_array()) {
+            Some(arr) if !arr.is_empty() => arr,
+            _ => {
+                self.empty_udi_dis += 1;
+                return;
+            }
+        };
+
+        for udi in udi_dis {
+            let code = get_field(udi, "udiDiCode");
+            if code.is_empty() {
+                self.udi_dis_without_code += 1;
+            } else {
+                match self.first_item_by_code.get_mut(&code) {
+                    None => {
+                        self.first_item_by_code.insert(code, Some(item_idx));
+                    }
+                    Some(seen) => {
+                        if seen.is_some_and(|first| first != item_idx) {
+                            *seen = None;
+                            self.duplicate_udi_di_codes += 1;
+                            if self.duplicate_udi_di_code_examples.len() < MAX_EXAMPLES {
+                                self.duplicate_udi_di_code_examples.push(code);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(tn_arr) = udi.get("tradeNames").and_then(|v| v.as_array()) {
+                for tn in tn_arr {
+                    *self
+                        .trade_name_languages
+                        .entry(trade_name_lang(tn))
+                        .or_default() += 1;
+                }
+            }
+        }
+    }
+
+    /// True if the dump has problems that should fail a pipeline.
+    pub fn has_hard_problems(&self) -> bool {
+        self.missing_basic_udi > 0 || self.duplicate_udi_di_codes > 0
+    }
+
+    /// Human-readable table for stdout.
+    pub fn print_table(&self) {
+        let mark = |n: usize, hard: bool| {
+            if n > 0 && hard {
+                "  FAIL"
+            } else if n > 0 {
+                "  warn"
+            } else {
+                ""
+            }
+        };
+        println!("{:<32} {:>10}", "check", "count");
+        println!("{}", "-".repeat(43));
+        println!("{:<32} {:>10}", "items", self.total_items);
+        println!(
+            "{:<32} {:>10}{}",
+            "items missing basicUdi",
+            self.missing_basic_udi,
+            mark(self.missing_basic_udi, true)
+        );
+        println!(
+            "{:<32} {:>10}{}",
+            "items with empty udiDis",
+            self.empty_udi_dis,
+            mark(self.empty_udi_dis, false)
+        );
+        println!(
+            "{:<32} {:>10}{}",
+            "udiDis entries without udiDiCode",
+            self.udi_dis_without_code,
+            mark(self.udi_dis_without_code, false)
+        );
+        println!(
+            "{:<32} {:>10}{}",
+            "duplicate udiDiCodes",
+            self.duplicate_udi_di_codes,
+            mark(self.duplicate_udi_di_codes, true)
+        );
+        if !self.duplicate_udi_di_code_examples.is_empty() {
+            println!("  e.g. {}", self.duplicate_udi_di_code_examples.join(", "));
+        }
+        println!();
+        println!("{:<32} {:>10}", "trade-name language", "entries");
+        println!("{}", "-".repeat(43));
+        for (lang, count) in &self.trade_name_languages {
+            println!("{:<32} {:>10}", lang, count);
+        }
+    }
+}
+
+/// `--stats` summary of a JSON input or a CSV export: sizes and the
+/// trade-name languages, for comparing harvests without writing anything.
+#[derive(Serialize)]
+pub struct DatasetStats {
+    /// Input items; `None` for a CSV export, which has rows only.
+    pub items: Option<usize>,
+    pub rows: usize,
+    pub distinct_udi_di_codes: usize,
+    /// Languages of the `tradeName_<LANG>` columns (or, in the long
+    /// layout, of the `tradeName_language` values), sorted.
+    pub trade_name_languages: Vec<String>,
+    pub columns: usize,
+}
+
+impl DatasetStats {
+    pub fn from_table(items: Option<usize>, headers: &[String], rows: &[Vec<String>]) -> Self {
+        let column = |name: &str| headers.iter().position(|h| h == name);
+        let distinct = |idx: Option<usize>| -> BTreeSet<&str> {
+            idx.map(|i| {
+                rows.iter()
+                    .filter_map(|r| r.get(i).map(String::as_str))
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+        };
+        let mut languages: BTreeSet<&str> = headers
+            .iter()
+            .filter(|h| {
+                ![PREFERRED_TRADE_NAME, TRADE_NAME_LANGUAGE, TRADE_NAME_TEXT].contains(&h.as_str())
+            })
+            .filter_map(|h| h.strip_prefix("tradeName_"))
+            .collect();
+        languages.extend(distinct(column(TRADE_NAME_LANGUAGE)));
+        DatasetStats {
+            items,
+            rows: rows.len(),
+            distinct_udi_di_codes: distinct(column("udiDiCode")).len(),
+            trade_name_languages: languages.into_iter().map(str::to_string).collect(),
+            columns: headers.len(),
+        }
+    }
+
+    pub fn print_table(&self) {
+        if let Some(items) = self.items {
+            println!("{:<32} {:>10}", "items", items);
+        }
+        println!("{:<32} {:>10}", "rows", self.rows);
+        println!(
+            "{:<32} {:>10}",
+            "distinct udiDiCodes", self.distinct_udi_di_codes
+        );
+        println!("{:<32} {:>10}", "columns", self.columns);
+        println!(
+            "{:<32} {:>10}",
+            "trade-name languages",
+            self.trade_name_languages.len()
+        );
+        if !self.trade_name_languages.is_empty() {
+            println!("  {}", self.trade_name_languages.join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|c| c.to_string()).collect()
+    }
+
+    /// Distinct non-empty udiDiCodes; languages from the `tradeName_<LANG>`
+    /// columns, without `tradeName_preferred`.
+    #[test]
+    fn dataset_stats_wide_layout() {
+        let headers = strings(&[
+            "udiDiCode",
+            "tradeName_DE",
+            "tradeName_FR",
+            "tradeName_preferred",
+        ]);
+        let rows = vec![
+            strings(&["0764001", "Pflaster", "", "Pflaster"]),
+            strings(&["0764001", "Pflaster", "Pansement", "Pflaster"]),
+            strings(&["0764002", "", "", ""]),
+            strings(&["", "Binde", "", "Binde"]),
+        ];
+        let stats = DatasetStats::from_table(Some(3), &headers, &rows);
+        assert_eq!(stats.items, Some(3));
+        assert_eq!(stats.rows, 4);
+        assert_eq!(stats.distinct_udi_di_codes, 2);
+        assert_eq!(stats.trade_name_languages, ["DE", "FR"]);
+        assert_eq!(stats.columns, 4);
+    }
+
+    /// In the long layout the languages are the `tradeName_language`
+    /// values; `tradeName_language`/`_text` are no languages themselves.
+    #[test]
+    fn dataset_stats_long_layout() {
+        let headers = strings(&["udiDiCode", TRADE_NAME_LANGUAGE, TRADE_NAME_TEXT]);
+        let rows = vec![
+            strings(&["0764001", "IT", "Cerotto"]),
+            strings(&["0764001", "DE", "Pflaster"]),
+            strings(&["0764002", "", ""]),
+        ];
+        let stats = DatasetStats::from_table(None, &headers, &rows);
+        assert_eq!(stats.items, None);
+        assert_eq!(stats.distinct_udi_di_codes, 2);
+        assert_eq!(stats.trade_name_languages, ["DE", "IT"]);
+    }
+}
@@ -0,0 +1,138 @@
+//! End-to-end conversion of `tests/fixtures/udi_items.json` through the
+//! library API: the exact headers and rows the CLI writes by default.
+
+use std::path::PathBuf;
+use swissdamed2sqlite::{data, download};
+
+fn load_fixture() -> Vec<serde_json::Value> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/udi_items.json");
+    download::load_json_files(&[path], download::InputFormat::Auto, None).expect("load fixture")
+}
+
+const EXPECTED_HEADERS: [&str; 17] = [
+    "basicUdi",
+    "companyName",
+    "deviceName",
+    "riskClass",
+    "sterile",
+    "shelfLifeMonths",
+    "maxPressureBar",
+    "productCodes",
+    "deviceDescriptions",
+    "manufacturer.name",
+    "manufacturer.country",
+    "udiDiCode",
+    "udi_status",
+    "tradeName_ANY",
+    "tradeName_DE",
+    "tradeName_FR",
+    "tradeName_IT",
+];
+
+fn expected_rows() -> Vec<Vec<String>> {
+    let catheter = [
+        "7640000000101A",
+        "Muster Medical AG",
+        "Urinary catheter",
+        "CLASS_IIA",
+        "TRUE",
+        "36",
+        "0.25",
+        "UC-10 | UC-12",
+        "DE: Einmalkatheter | FR: Sonde urinaire",
+        "Muster Medical AG",
+        "CH",
+    ];
+    let rows: [Vec<&str>; 4] = [
+        // Repeated same-language trade names are joined
+        [
+            &catheter[..],
+            &[
+                "07640000001012",
+                "ON_THE_MARKET",
+                "",
+                "Kathi | Kathi Soft",
+                "Kathi",
+                "",
+            ],
+        ]
+        .concat(),
+        // Second udiDis entry of the same item: null status → empty
+        [
+            &catheter[..],
+            &["07640000001029", "", "", "", "", "Catetere"],
+        ]
+        .concat(),
+        // Nulls and an empty array are empty cells; a trade name without a
+        // language lands in tradeName_ANY, trimmed
+        vec![
+            "7640000000102B",
+            "Exemple SA",
+            "",
+            "CLASS_I",
+            "FALSE",
+            "",
+            "",
+            "",
+            "",
+            "Exemple SA",
+            "",
+            "07640000001036",
+            "",
+            "Pflaster\nsteril",
+            "",
+            "",
+            "",
+        ],
+        // An item without udiDis still gets one row
+        [
+            &["7640000000103C", "Ohne UDI GmbH", "Spacer"][..],
+            &[""; 14],
+        ]
+        .concat(),
+    ];
+    rows.iter()
+        .map(|row| row.iter().map(|c| c.to_string()).collect())
+        .collect()
+}
+
+#[test]
+fn convert_values_headers_and_rows() {
+    let values = load_fixture();
+    assert_eq!(values.len(), 3);
+
+    let (headers, rows) = swissdamed2sqlite::convert_values(&values);
+    assert_eq!(headers, EXPECTED_HEADERS);
+    assert_eq!(rows, expected_rows());
+}
+
+/// The streaming `RowBuilder` behind the default export agrees with the
+/// two-pass `collect_headers` + `build_rows`.
+#[test]
+fn row_builder_matches_two_pass_conversion() {
+    let values = load_fixture();
+    let mut builder = data::RowBuilder::new(data::ValueFormat::DEFAULT);
+    for item in &values {
+        builder.push(item);
+    }
+    assert_eq!(builder.item_count(), 3);
+    let headers = builder.headers();
+    assert_eq!(headers, EXPECTED_HEADERS);
+    assert_eq!(builder.into_rows(&headers), expected_rows());
+}
+
+#[test]
+fn value_to_string_formats() {
+    use serde_json::json;
+
+    assert_eq!(data::value_to_string(&json!(null)), "");
+    assert_eq!(data::value_to_string(&json!(true)), "TRUE");
+    assert_eq!(data::value_to_string(&json!(42)), "42");
+    assert_eq!(data::value_to_string(&json!(0.1 + 0.2)), "0.3");
+    assert_eq!(data::value_to_string(&json!("  padded  ")), "padded");
+    assert_eq!(data::value_to_string(&json!(["a", 1, null])), "a | 1");
+    assert_eq!(
+        data::value_to_string(&json!([{ "language": "EN", "textValue": "Plaster" }])),
+        "EN: Plaster"
+    );
+}
@@ -0,0 +1,106 @@
+//! Peak heap use of the streaming CSV writer. A test binary of its own,
+//! since measuring needs a tracking `#[global_allocator]` that must not
+//! replace the allocator of the other tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fs;
+use std::io::Read;
+use swissdamed2sqlite::export::{write_csv_with, Compression, CsvOptions};
+
+/// Allocator tracking the live heap bytes of the current thread, so a test
+/// measures its own peak even while the harness runs others in parallel.
+struct Tracking;
+
+thread_local! {
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record(delta: isize) {
+    // try_with: thread-locals may already be gone during thread exit.
+    let _ = LIVE.try_with(|live| {
+        live.set(live.get() + delta);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+    });
+}
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking;
+
+/// Peak live bytes of `f` above what was live when it started.
+fn peak_during(f: impl FnOnce()) -> usize {
+    let start = LIVE.with(Cell::get);
+    PEAK.with(|peak| peak.set(start));
+    f();
+    (PEAK.with(Cell::get) - start).max(0) as usize
+}
+
+/// Streaming a large CSV keeps the writer's heap use at its buffers (far
+/// below the file size) and produces the same bytes as encoding the whole
+/// file in memory; the gzip variant decodes to them too.
+#[test]
+fn csv_is_streamed_to_disk() {
+    let headers: Vec<String> = (0..10).map(|c| format!("column_{}", c)).collect();
+    let rows: Vec<Vec<String>> = (0..50_000)
+        .map(|r| {
+            (0..10)
+                .map(|c| format!("row {} \"cell\" {},\nsecond line", r, c))
+                .collect()
+        })
+        .collect();
+    let path = std::env::temp_dir().join(format!(
+        "swissdamed2sqlite_stream_test_{}.csv",
+        std::process::id()
+    ));
+    let filename = path.to_string_lossy().to_string();
+
+    let opts = CsvOptions::default();
+    let mut written = String::new();
+    let peak = peak_during(|| {
+        written = write_csv_with(&headers, &rows, &filename, &opts).expect("write csv");
+    });
+    let on_disk = fs::read(&written).unwrap();
+    assert!(on_disk.len() > 10_000_000, "{} bytes", on_disk.len());
+    assert!(peak < 256 * 1024, "peak heap {} bytes while writing", peak);
+
+    let mut expected = b"\xEF\xBB\xBF".to_vec();
+    let mut wtr = csv::WriterBuilder::new().from_writer(&mut expected);
+    wtr.write_record(&headers).unwrap();
+    for row in &rows {
+        wtr.write_record(row).unwrap();
+    }
+    drop(wtr);
+    assert!(
+        on_disk == expected,
+        "streamed CSV differs from the in-memory encoding"
+    );
+
+    let gz_opts = CsvOptions {
+        compress: Some(Compression::Gzip),
+        ..CsvOptions::default()
+    };
+    let packed = write_csv_with(&headers, &rows, &filename, &gz_opts).expect("write gzip");
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(fs::File::open(&packed).unwrap())
+        .read_to_end(&mut decoded)
+        .unwrap();
+    fs::remove_file(&written).ok();
+    fs::remove_file(&packed).ok();
+    assert!(
+        decoded == expected,
+        "gzip CSV does not decode to the plain bytes"
+    );
+}